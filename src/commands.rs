@@ -0,0 +1,289 @@
+//! Keep the colon commands of the REPL.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+Colon commands are kept in a registry by name.  A command can also be reached by any of its
+aliases, or by a prefix of its name or of an alias, provided the prefix picks out just one
+command.  A name or alias given in full always picks out its command, even if it is also the
+prefix of another.
+
+Each command carries the usage and description shown by `:help`, and the least and most
+number of arguments it takes, so that a wrong number of arguments can be reported before the
+command runs.
+
+A command line is split into words at white space.  Part of a word can be enclosed in double
+quotes to include white space, and inside the quotes the escapes written by `util::escape` are
+understood, so `:load "my files/a.eli"` and `:set name "a\"b"` work.  A command can take an ELI
+term as its last argument; the term is the rest of the line, taken as it is, so that quotes in
+the term are left for the ELI parser.
+*/
+
+use std::collections::BTreeMap;
+use session::Session;
+use util::{unescape, UnescapeError};
+
+/// The return result from a callback.  Errors are returned as a string in an `Err` result, while
+/// success is indicated by an `Ok` result holding the text to print, which may be empty.  The
+/// first line of an error is its message, and any further lines are shown as notes.
+pub type CallbackResult = Result<String, String>;
+
+/// A callback is a function mapping the session and the arguments to a return value.  The
+/// session can be changed by the callback, and its quit flag ends the REPL.  The arguments are
+/// the words of the command line, starting with the command itself.  The result indicates
+/// success or an error.
+pub type Callback = Box<dyn Fn(&mut Session, &[Argument]) -> CallbackResult>;
+
+/// Struct to hold a colon command definition.
+pub struct ColonCommand {
+    /// The name of the command.
+    pub name: &'static str,
+    /// Other names for the command.
+    pub aliases: Vec<&'static str>,
+    /// How to invoke the command, such as `:load PATH`.
+    pub usage: &'static str,
+    /// What the command does, in a sentence.
+    pub description: &'static str,
+    /// The least number of arguments.
+    pub min_args: usize,
+    /// The most number of arguments, or `None` if there is no limit.
+    pub max_args: Option<usize>,
+    /// Whether the last argument is an ELI term that runs to the end of the line.  Such a
+    /// command should take a fixed number of arguments.
+    pub term: bool,
+    /// The action to perform when the command is detected.
+    pub action: Callback,
+}
+
+impl ColonCommand {
+    /// Make a new command that takes no arguments and has no aliases.
+    ///
+    ///   * `name`: The name of the command.
+    ///   * `usage`: How to invoke the command.
+    ///   * `description`: What the command does.
+    ///   * `action`: The action to perform.
+    ///
+    pub fn new(name: &'static str, usage: &'static str, description: &'static str,
+        action: Callback) -> Self {
+        ColonCommand {
+            name,
+            aliases: vec![],
+            usage,
+            description,
+            min_args: 0,
+            max_args: Some(0),
+            term: false,
+            action,
+        }
+    }
+
+    /// Give the command other names.
+    pub fn with_aliases(mut self, aliases: &[&'static str]) -> Self {
+        self.aliases = aliases.to_vec();
+        self
+    }
+
+    /// Set the least and most number of arguments.  Use `None` for no upper limit.
+    pub fn with_arity(mut self, min_args: usize, max_args: Option<usize>) -> Self {
+        self.min_args = min_args;
+        self.max_args = max_args;
+        self
+    }
+
+    /// Make the last argument an ELI term that runs to the end of the line.  The command then
+    /// takes exactly `words` arguments before the term.
+    pub fn with_term(mut self, words: usize) -> Self {
+        self.min_args = words + 1;
+        self.max_args = Some(words + 1);
+        self.term = true;
+        self
+    }
+
+    /// Split a command line into words for this command.  The command itself is the first
+    /// word.  See `split_arguments`.
+    pub fn split(&self, line: &str) -> Result<Vec<Argument>, UnescapeError> {
+        split_arguments(line, if self.term { Some(self.min_args) } else { None })
+    }
+
+    /// Decide whether the command takes the given number of arguments.
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min_args && self.max_args.is_none_or(|max| count <= max)
+    }
+
+    /// Describe the command in full, as shown by `:help COMMAND`.
+    pub fn help(&self) -> String {
+        let mut text = format!("usage: {}\n{}\n", self.usage, self.description);
+        if !self.aliases.is_empty() {
+            let aliases: Vec<String> = self.aliases.iter().map(|alias| format!(":{}", alias))
+                .collect();
+            text.push_str(&format!("aliases: {}\n", aliases.join(", ")));
+        }
+        text
+    }
+}
+
+/// The colon commands, by name.
+#[derive(Default)]
+pub struct CommandRegistry {
+    /// The commands, by name.
+    commands: BTreeMap<&'static str, ColonCommand>,
+    /// The name of the command for each alias.
+    aliases: BTreeMap<&'static str, &'static str>,
+}
+
+impl CommandRegistry {
+    /// Make a new, empty registry.
+    pub fn new() -> Self {
+        CommandRegistry::default()
+    }
+
+    /// Add a command.  If a command with the same name is already present, it is replaced and
+    /// returned.
+    pub fn add(&mut self, command: ColonCommand) -> Option<ColonCommand> {
+        let prior = self.commands.remove(command.name);
+        if let Some(ref prior) = prior {
+            for alias in &prior.aliases {
+                self.aliases.remove(alias);
+            }
+        }
+        for alias in &command.aliases {
+            self.aliases.insert(alias, command.name);
+        }
+        self.commands.insert(command.name, command);
+        prior
+    }
+
+    /// Get a command by its full name or by one of its aliases.
+    pub fn get(&self, name: &str) -> Option<&ColonCommand> {
+        let name = self.aliases.get(name).cloned().unwrap_or(name);
+        self.commands.get(name)
+    }
+
+    /// Find the command a name refers to.  The name may be a full name or alias, or a prefix
+    /// of exactly one command's name or aliases.  Otherwise the names of every command the
+    /// prefix could refer to are returned, in order, and the list is empty if there are none.
+    pub fn lookup(&self, name: &str) -> Result<&ColonCommand, Vec<&'static str>> {
+        if let Some(command) = self.get(name) {
+            return Ok(command);
+        }
+        let mut candidates: Vec<&'static str> = self.commands.values()
+            .filter(|command| {
+                command.name.starts_with(name) ||
+                    command.aliases.iter().any(|alias| alias.starts_with(name))
+            })
+            .map(|command| command.name)
+            .collect();
+        if candidates.len() == 1 {
+            Ok(&self.commands[candidates[0]])
+        } else {
+            candidates.sort();
+            Err(candidates)
+        }
+    }
+
+    /// Iterate over the commands, in order by name.
+    pub fn iter(&self) -> ::std::collections::btree_map::Values<'_, &'static str, ColonCommand> {
+        self.commands.values()
+    }
+
+    /// Describe every command in a line each, as shown by `:help`.
+    pub fn help(&self) -> String {
+        let width = self.iter().map(|command| command.usage.chars().count()).max().unwrap_or(0);
+        let mut text = String::new();
+        for command in self.iter() {
+            text.push_str(&format!("{:width$}  {}\n", command.usage, command.description,
+                width = width));
+        }
+        text
+    }
+}
+
+/// A word of a command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Argument {
+    /// The text of the word, with quotes removed and escapes replaced.
+    pub text: String,
+    /// The offset of the word in the line, in characters.
+    pub offset: usize,
+    /// The number of characters the word takes in the line.
+    pub width: usize,
+}
+
+/// Split a command line into words.  Words are separated by white space, and a part of a word
+/// in double quotes may contain white space and the escapes understood by `util::unescape`.
+///
+///   * `line`: The command line.
+///   * `words`: If given, after this many words the rest of the line, without its surrounding
+///     white space, is a final argument taken as it is.
+///
+/// An unterminated quote or a malformed escape is reported with its offset in the line.
+pub fn split_arguments(line: &str, words: Option<usize>) -> Result<Vec<Argument>, UnescapeError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut arguments = vec![];
+    let mut index = 0;
+    loop {
+        while index < chars.len() && chars[index].is_whitespace() {
+            index += 1;
+        }
+        if index == chars.len() {
+            break;
+        }
+        let start = index;
+        if words == Some(arguments.len()) {
+            let mut end = chars.len();
+            while chars[end - 1].is_whitespace() {
+                end -= 1;
+            }
+            let text = chars[start..end].iter().collect();
+            arguments.push(Argument { text, offset: start, width: end - start });
+            break;
+        }
+        let mut text = String::new();
+        while index < chars.len() && !chars[index].is_whitespace() {
+            if chars[index] != '"' {
+                text.push(chars[index]);
+                index += 1;
+                continue;
+            }
+            // Find the closing quote, skipping escaped characters, and then unescape.
+            let open = index;
+            index += 1;
+            while index < chars.len() && chars[index] != '"' {
+                index += if chars[index] == '\\' { 2 } else { 1 };
+            }
+            if index >= chars.len() {
+                return Err(UnescapeError {
+                    offset: open,
+                    width: chars.len() - open,
+                    message: "unterminated quote".to_string(),
+                });
+            }
+            let quoted: String = chars[open + 1..index].iter().collect();
+            index += 1;
+            match unescape(&quoted, '"') {
+                Ok(part) => text.push_str(&part),
+                Err(mut err) => {
+                    err.offset += open + 1;
+                    return Err(err);
+                }
+            }
+        }
+        arguments.push(Argument { text, offset: start, width: index - start });
+    }
+    Ok(arguments)
+}
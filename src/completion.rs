@@ -0,0 +1,228 @@
+//! Complete partial lines in the REPL.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+A completer offers ways to finish the line being typed.  What it offers depends on where the
+line ends.
+
+  * In the name of a colon command, the names and aliases of the commands.
+  * In the path given to `:load`, the files and directories that match, looked for in the
+    current directory and then in the include path.
+  * In the command given to `:help`, the names and aliases of the commands.
+  * In a name anywhere else, the named roots and the declared operators, or the REPL variables
+    if the name starts with a dollar sign.
+
+Each completion is the whole line, since that is what linenoise shows.  A completer also gives
+a hint, which is the usage of the colon command being typed.  Linenoise cannot show hints, so
+when there is nothing to complete, the candidates offered for TAB are the whole usage instead.
+Pressing TAB again goes back to the line as it was typed.
+
+A completer is a snapshot of a session, made before each line is read, so that it can be kept
+by a line source without borrowing the session.
+*/
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use commands::split_arguments;
+use session::Session;
+use util::escape_with;
+
+/// Offer completions and hints for a partial line.
+#[derive(Clone, Debug, Default)]
+pub struct Completer {
+    /// The names and aliases of the colon commands, each with the usage of its command.
+    commands: Vec<(String, String)>,
+    /// The names of the named roots.
+    roots: Vec<String>,
+    /// The names of the declared operators.
+    operators: Vec<String>,
+    /// The names of the REPL variables, without the dollar sign.
+    variables: Vec<String>,
+    /// The directories searched for files after the current directory.
+    path: Vec<PathBuf>,
+}
+
+impl Completer {
+    /// Make a completer for the current state of a session.
+    pub fn new(session: &Session) -> Self {
+        let mut commands = vec![];
+        for command in session.commands.iter() {
+            commands.push((command.name.to_string(), command.usage.to_string()));
+            for alias in &command.aliases {
+                commands.push((alias.to_string(), command.usage.to_string()));
+            }
+        }
+        commands.sort();
+        let universe = &session.universe;
+        let mut roots: Vec<String> = universe.factory().named_terms().cloned().collect();
+        roots.sort();
+        let mut variables: Vec<String> =
+            session.bindings().into_iter().map(|(name, _)| name).collect();
+        variables.sort();
+        let mut operators: Vec<String> =
+            universe.operators().iter().map(|operator| operator.name.clone()).collect();
+        operators.sort();
+        Completer {
+            commands,
+            roots,
+            operators,
+            variables,
+            path: session.loader.path().to_vec(),
+        }
+    }
+
+    /// Get the completions of a line, each of which is the whole line, in order.
+    pub fn complete(&self, line: &str) -> Vec<String> {
+        if !line.starts_with(':') {
+            return self.complete_name(line);
+        }
+        match line.find(char::is_whitespace) {
+            None => self.commands.iter()
+                .filter(|(name, _)| name.starts_with(&line[1..]))
+                .map(|(name, _)| format!(":{} ", name))
+                .collect(),
+            Some(end) => {
+                let usage = self.usage(&line[1..end]).unwrap_or("");
+                match usage.split_whitespace().nth(1) {
+                    Some("PATH") => self.complete_path(line),
+                    Some("TERM") => self.complete_name(line),
+                    Some("[COMMAND]") => self.complete_command(line),
+                    _ => vec![],
+                }
+            }
+        }
+    }
+
+    /// Get the hint for a line: the rest of the usage of the colon command that is the whole
+    /// line so far.
+    pub fn hint(&self, line: &str) -> Option<String> {
+        if !line.starts_with(':') {
+            return None;
+        }
+        let name = line[1..].trim_end();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return None;
+        }
+        let usage = self.usage(name)?;
+        let rest = &usage[usage.find(' ')?..];
+        Some(if line.ends_with(' ') { rest.trim_start() } else { rest }.to_string())
+    }
+
+    /// Get the candidates to offer when TAB is pressed at the end of a line: the completions,
+    /// or if there are none, the whole usage of the colon command being typed, if there are
+    /// arguments still to give.
+    pub fn candidates(&self, line: &str) -> Vec<String> {
+        let completions = self.complete(line);
+        if !completions.is_empty() {
+            return completions;
+        }
+        self.hint(line).map(|hint| format!("{}{}", line, hint)).into_iter().collect()
+    }
+
+    /// Get the usage of the command with the given name or alias, if there is one.
+    fn usage(&self, name: &str) -> Option<&str> {
+        self.commands.iter().find(|(command, _)| command == name).map(|(_, usage)| &usage[..])
+    }
+
+    /// Complete the name at the end of a line.  A name after a dollar sign is a REPL variable,
+    /// and any other is a named root or an operator.
+    fn complete_name(&self, line: &str) -> Vec<String> {
+        let start = line.char_indices().rev()
+            .take_while(|&(_, ch)| ch.is_alphanumeric() || ch == '_')
+            .last()
+            .map_or(line.len(), |(index, _)| index);
+        let (head, word) = line.split_at(start);
+        let names: Vec<&String> = if head.ends_with('$') {
+            self.variables.iter().collect()
+        } else if word.is_empty() || word.starts_with(|ch: char| ch.is_ascii_digit()) {
+            vec![]
+        } else {
+            self.roots.iter().chain(self.operators.iter()).collect()
+        };
+        names.into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| format!("{}{}", head, name))
+            .collect()
+    }
+
+    /// Complete the name of the command given to another command, such as `:help`.
+    fn complete_command(&self, line: &str) -> Vec<String> {
+        let start = line.rfind(char::is_whitespace).map_or(line.len(), |at| at + 1);
+        let (head, word) = line.split_at(start);
+        if head.split_whitespace().count() != 1 {
+            return vec![];
+        }
+        self.commands.iter()
+            .filter(|(name, _)| name.starts_with(word))
+            .map(|(name, _)| format!("{}{}", head, name))
+            .collect()
+    }
+
+    /// Complete the path given to a command.  A path with white space or quotes in it is
+    /// quoted.
+    fn complete_path(&self, line: &str) -> Vec<String> {
+        // The path may be in quotes that are not closed yet.
+        let closed = format!("{}\"", line);
+        let words = match split_arguments(line, None).or_else(|_| split_arguments(&closed, None)) {
+            Ok(words) => words,
+            Err(_) => return vec![],
+        };
+        let (head, partial) = match words.get(1) {
+            None => (line.trim_end(), ""),
+            Some(word) if words.len() == 2 && word.offset + word.width >= line.chars().count() => {
+                let start = line.char_indices().nth(word.offset).map_or(line.len(), |(at, _)| at);
+                (line[..start].trim_end(), &word.text[..])
+            }
+            _ => return vec![],
+        };
+        let (dir, prefix) = match partial.rfind('/') {
+            Some(slash) => (&partial[..slash + 1], &partial[slash + 1..]),
+            None => ("", partial),
+        };
+        let mut bases = vec![PathBuf::new()];
+        if !Path::new(dir).is_absolute() {
+            bases.extend(self.path.iter().cloned());
+        }
+        let mut found = vec![];
+        for base in bases {
+            let entries = match fs::read_dir(base.join(if dir.is_empty() { "." } else { dir })) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                let hidden = name.starts_with('.') && !prefix.starts_with('.');
+                if hidden || !name.starts_with(prefix) {
+                    continue;
+                }
+                let slash = if entry.path().is_dir() { "/" } else { "" };
+                found.push(format!("{}{}{}", dir, name, slash));
+            }
+        }
+        found.sort();
+        found.dedup();
+        found.into_iter().map(|path| {
+            if path.contains(|ch: char| ch.is_whitespace() || ch == '"' || ch == '\\') {
+                format!("{} \"{}\"", head, escape_with(&path, '"', false).0)
+            } else {
+                format!("{} {}", head, path)
+            }
+        }).collect()
+    }
+}
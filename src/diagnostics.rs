@@ -0,0 +1,374 @@
+//! Report errors, warnings, and notes with source context.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+A diagnostic is a message about some input, together with the places in the input that the
+message is about.  Places are given by a locus, so a diagnostic can point at a file, at a line
+entered on the console, or at nothing in particular (an internal locus).
+
+When a diagnostic is rendered, the source line for each label is looked up in a `Sources`
+instance and printed with the offending columns underlined.  The primary label is underlined
+with carets (^) and secondary labels with dashes (-).  A line from a different file than the
+first line shown is headed by its locus.  A rendered diagnostic looks like this.
+
+    error: the command :frob was not recognized
+     --> 3:1
+      |
+    3 | :frob now
+      | ^^^^^ unknown command
+      = note: use :help to list the commands
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use terms::Locus;
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something went wrong and the requested action was not performed.
+    Error,
+    /// Something is suspicious, but the requested action was performed.
+    Warning,
+    /// Additional information.
+    Note,
+    /// A suggestion about how to fix a problem.
+    Help,
+}
+
+impl Severity {
+    /// Get the ANSI escape sequence used to colour this severity.
+    fn colour(&self) -> &'static str {
+        match *self {
+            Severity::Error => "\x1b[1;31m",
+            Severity::Warning => "\x1b[1;33m",
+            Severity::Note => "\x1b[1;32m",
+            Severity::Help => "\x1b[1;36m",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, form: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Error => write!(form, "error"),
+            Severity::Warning => write!(form, "warning"),
+            Severity::Note => write!(form, "note"),
+            Severity::Help => write!(form, "help"),
+        }
+    }
+}
+
+/// A label attaches a message to a span of columns on one line of the input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Label {
+    /// Where the span starts.  Lines and columns are counted from one.
+    pub locus: Locus,
+    /// The number of columns in the span.  A zero width is shown as a single column.
+    pub width: u32,
+    /// The message to show next to the underline.  This may be empty.
+    pub message: String,
+}
+
+impl Label {
+    /// Make a new label.
+    ///   * `locus`: Where the span starts.
+    ///   * `width`: The number of columns in the span.
+    ///   * `message`: The message to show next to the underline.
+    pub fn new(locus: Locus, width: u32, message: &str) -> Self {
+        Label {
+            locus,
+            width,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// An error, warning, or other message about the input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// How serious the diagnostic is.
+    pub severity: Severity,
+    /// The main message.
+    pub message: String,
+    /// The place the diagnostic is mainly about, if any.
+    pub primary: Option<Label>,
+    /// Other places that are relevant.
+    pub secondary: Vec<Label>,
+    /// Additional notes shown after the source snippets.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Make a new diagnostic with the given severity and message and no labels.
+    pub fn new(severity: Severity, message: &str) -> Self {
+        Diagnostic {
+            severity,
+            message: message.to_string(),
+            primary: None,
+            secondary: vec![],
+            notes: vec![],
+        }
+    }
+
+    /// Make a new error.
+    pub fn error(message: &str) -> Self {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    /// Make a new warning.
+    pub fn warning(message: &str) -> Self {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    /// Set the primary label and return the diagnostic.
+    pub fn with_primary(mut self, locus: Locus, width: u32, message: &str) -> Self {
+        self.primary = Some(Label::new(locus, width, message));
+        self
+    }
+
+    /// Add a secondary label and return the diagnostic.
+    pub fn with_secondary(mut self, locus: Locus, width: u32, message: &str) -> Self {
+        self.secondary.push(Label::new(locus, width, message));
+        self
+    }
+
+    /// Add a note and return the diagnostic.
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    /// Determine if this diagnostic is an error.
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Render the diagnostic as plain text, including source snippets for any labels whose
+    /// source line can be found.  The result ends with a newline.
+    pub fn render(&self, sources: &Sources) -> String {
+        self.render_with(sources, &Palette::plain())
+    }
+
+    /// Render the diagnostic using ANSI escape sequences to colour the output.  This is
+    /// otherwise identical to `render`.
+    pub fn render_ansi(&self, sources: &Sources) -> String {
+        self.render_with(sources, &Palette::ansi(self.severity))
+    }
+
+    fn render_with(&self, sources: &Sources, pal: &Palette) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("{}{}{}{}: {}{}\n", pal.severity, self.severity, pal.reset,
+            pal.bold, self.message, pal.reset));
+
+        // Collect the labels, primary first, and work out how wide the gutter must be.
+        let mut labels: Vec<(&Label, bool)> = vec![];
+        if let Some(ref label) = self.primary {
+            labels.push((label, true));
+        }
+        for label in &self.secondary {
+            labels.push((label, false));
+        }
+        let width = labels.iter()
+            .map(|&(label, _)| line_of(&label.locus).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(width);
+
+        // Tell the user where to look.
+        let mut located = None;
+        if let Some(ref label) = self.primary {
+            if label.locus != Locus::Internal {
+                out.push_str(&format!("{}{}-->{} {}\n", gutter, pal.gutter, pal.reset,
+                    label.locus));
+                located = Some(origin_of(&label.locus));
+            }
+        }
+
+        // Show each source line once, with every label on it underneath.  Lines are shown
+        // in the order their first label was given.
+        let mut shown: Vec<(Option<String>, u32)> = vec![];
+        for &(label, _) in &labels {
+            let key = (origin_of(&label.locus), line_of(&label.locus));
+            if label.locus == Locus::Internal || shown.contains(&key) {
+                continue;
+            }
+            let text = match sources.get_line(&label.locus) {
+                Some(text) => text,
+                None => continue,
+            };
+            if located.is_none() {
+                out.push_str(&format!("{}{}-->{} {}\n", gutter, pal.gutter, pal.reset,
+                    label.locus));
+                located = Some(key.0.clone());
+            } else if located.as_ref() != Some(&key.0) {
+                // A line from another file, or from the console, says where it is from.
+                out.push_str(&format!("{}{}:::{} {}\n", gutter, pal.gutter, pal.reset,
+                    label.locus));
+            }
+            out.push_str(&format!("{} {}|{}\n", gutter, pal.gutter, pal.reset));
+            out.push_str(&format!("{}{:>w$} |{} {}\n", pal.gutter, key.1, pal.reset, text,
+                w = width));
+            for &(other, primary) in &labels {
+                if origin_of(&other.locus) != key.0 || line_of(&other.locus) != key.1 {
+                    continue;
+                }
+                let (mark, colour) = if primary { ('^', pal.severity) } else { ('-', pal.gutter) };
+                let column = column_of(&other.locus);
+                let mut underline = String::new();
+                for ch in text.chars().take(column.saturating_sub(1) as usize) {
+                    underline.push(if ch == '\t' { '\t' } else { ' ' });
+                }
+                let mut line = format!("{} {}|{} {}{}", gutter, pal.gutter, pal.reset,
+                    underline, colour);
+                for _ in 0..other.width.max(1) {
+                    line.push(mark);
+                }
+                if !other.message.is_empty() {
+                    line.push(' ');
+                    line.push_str(&other.message);
+                }
+                line.push_str(pal.reset);
+                out.push_str(&line);
+                out.push('\n');
+            }
+            shown.push(key);
+        }
+
+        // Secondary labels whose lines could not be shown are listed by locus instead.
+        for label in &self.secondary {
+            let key = (origin_of(&label.locus), line_of(&label.locus));
+            if !shown.contains(&key) && label.locus != Locus::Internal {
+                out.push_str(&format!("{} {}= see{} {}: {}\n", gutter, pal.bold, pal.reset,
+                    label.locus, label.message));
+            }
+        }
+
+        for note in &self.notes {
+            out.push_str(&format!("{} {}= note:{} {}\n", gutter, pal.bold, pal.reset, note));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, form: &mut fmt::Formatter) -> fmt::Result {
+        match self.primary {
+            Some(ref label) if label.locus != Locus::Internal => {
+                write!(form, "{}: {}: {}", label.locus, self.severity, self.message)
+            }
+            _ => write!(form, "{}: {}", self.severity, self.message),
+        }
+    }
+}
+
+/// The escape sequences used when rendering.  These are all empty for plain text.
+struct Palette {
+    severity: &'static str,
+    gutter: &'static str,
+    bold: &'static str,
+    reset: &'static str,
+}
+
+impl Palette {
+    fn plain() -> Self {
+        Palette { severity: "", gutter: "", bold: "", reset: "" }
+    }
+
+    fn ansi(severity: Severity) -> Self {
+        Palette {
+            severity: severity.colour(),
+            gutter: "\x1b[1;34m",
+            bold: "\x1b[1m",
+            reset: "\x1b[0m",
+        }
+    }
+}
+
+/// Get the name of the file a locus refers to, or `None` for the console.
+fn origin_of(locus: &Locus) -> Option<String> {
+    match *locus {
+        Locus::File(ref name, _, _) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Get the line of a locus, or zero if it has none.
+fn line_of(locus: &Locus) -> u32 {
+    match *locus {
+        Locus::Internal => 0,
+        Locus::Console(line, _) => line,
+        Locus::File(_, line, _) => line,
+    }
+}
+
+/// Get the column of a locus, or zero if it has none.
+fn column_of(locus: &Locus) -> u32 {
+    match *locus {
+        Locus::Internal => 0,
+        Locus::Console(_, column) => column,
+        Locus::File(_, _, column) => column,
+    }
+}
+
+/// The source text that diagnostics can quote.  Console lines and files are stored
+/// separately, matching the two kinds of locus that refer to input.
+#[derive(Clone, Debug, Default)]
+pub struct Sources {
+    /// Lines entered at the console, keyed by line number.
+    console: HashMap<u32, String>,
+    /// The lines of each file, keyed by file name.
+    files: HashMap<String, Vec<String>>,
+}
+
+impl Sources {
+    /// Make a new, empty collection of sources.
+    pub fn new() -> Self {
+        Sources::default()
+    }
+
+    /// Record the text of a console line.  If the text spans several lines, they are
+    /// numbered consecutively starting from `line`.
+    pub fn add_console(&mut self, line: u32, text: &str) {
+        for (index, part) in text.lines().enumerate() {
+            self.console.insert(line + index as u32, part.to_string());
+        }
+    }
+
+    /// Record the full text of a file.
+    pub fn add_file(&mut self, name: &str, text: &str) {
+        self.files.insert(name.to_string(), text.lines().map(|line| line.to_string()).collect());
+    }
+
+    /// Get the source line a locus refers to, if it is known.
+    pub fn get_line(&self, locus: &Locus) -> Option<&str> {
+        match *locus {
+            Locus::Internal => None,
+            Locus::Console(line, _) => self.console.get(&line).map(|text| text.as_str()),
+            Locus::File(ref name, line, _) => {
+                if line == 0 {
+                    return None;
+                }
+                self.files.get(name)
+                    .and_then(|lines| lines.get(line as usize - 1))
+                    .map(|text| text.as_str())
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! Define the errors reported by the library.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+use std::error;
+use std::fmt;
+use std::io;
+use diagnostics::Diagnostic;
+
+/// The errors that can be reported by the library.  Failures that are the fault of the input
+/// carry a diagnostic that can be rendered with the offending source.  Diagnostics are boxed
+/// to keep results small.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing failed.
+    Io(io::Error),
+    /// The input could not be parsed.
+    Parse(Box<Diagnostic>),
+    /// A term is not well-typed.
+    Type(Box<Diagnostic>),
+    /// Rewriting stopped because the limit on the number of rewrites was reached.
+    RewriteLimit(u64),
+    /// Rewriting stopped because guards were nested more deeply than the given depth.
+    GuardDepth(usize),
+    /// The configuration could not be located or is not valid.
+    Config(String),
+}
+
+/// The result type used throughout the library.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl Error {
+    /// Get the diagnostic for this error, if it has one.
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match *self {
+            Error::Parse(ref diagnostic) | Error::Type(ref diagnostic) => Some(diagnostic),
+            _ => None,
+        }
+    }
+
+    /// Convert this error into a diagnostic, so that every error can be reported the same way.
+    pub fn into_diagnostic(self) -> Diagnostic {
+        match self {
+            Error::Parse(diagnostic) | Error::Type(diagnostic) => *diagnostic,
+            other => Diagnostic::error(&other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, form: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref err) => write!(form, "{}", err),
+            Error::Parse(ref diagnostic) => write!(form, "{}", diagnostic),
+            Error::Type(ref diagnostic) => write!(form, "{}", diagnostic),
+            Error::RewriteLimit(limit) => {
+                write!(form, "rewriting stopped after reaching the limit of {} rewrites", limit)
+            }
+            Error::GuardDepth(depth) => {
+                write!(form, "rewriting stopped because guards were nested more than {} deep",
+                    depth)
+            }
+            Error::Config(ref msg) => write!(form, "{}", msg),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,251 @@
+//! Sources of input lines for the REPL.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+The REPL reads its input through the `LineSource` trait, so that it can be driven by a terminal,
+by a plain stream, or by a test.  A line source also keeps the history of entries.
+
+The linenoise functions for history take a path or a line as a C string, but do not keep the C
+string alive for the call, so the linenoise source calls the underlying functions itself.
+
+Linenoise takes its completion callback as a plain function, with no data of its own.  The
+linenoise source keeps its completer, and lends it to a thread-local for the callback only while
+a line is read.  The linenoise bundled with the crate cannot show hints, so when there is nothing
+to complete, the usage of the colon command being typed is offered as the completion instead.
+*/
+
+extern crate linenoise;
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::io::{self, BufRead, IsTerminal};
+use completion::Completer;
+
+thread_local! {
+    /// The completer of the linenoise source that is reading a line, if any.
+    static READING: RefCell<Option<Completer>> = const { RefCell::new(None) };
+}
+
+/// Complete a line for linenoise with the completer of the source that is reading it.
+fn complete(line: &str) -> Vec<String> {
+    READING.with(|reading| match *reading.borrow() {
+        Some(ref completer) => completer.candidates(line),
+        None => vec![],
+    })
+}
+
+/// A source of input lines, with a history of the entries read.
+pub trait LineSource {
+    /// Show the prompt and read a line.  The line is returned without its line ending, or
+    /// `None` is returned at the end of the input.
+    fn read_line(&mut self, prompt: &str) -> Option<String>;
+
+    /// Add an entry to the history.  An entry may span several lines.
+    fn add_history(&mut self, entry: &str);
+
+    /// Get the history, oldest entry first.
+    fn history(&self) -> Vec<String>;
+
+    /// Clear the screen, if there is one.
+    fn clear_screen(&mut self) {}
+
+    /// Use a completer for the lines read from now on.  A source that cannot complete lines
+    /// ignores it.
+    fn set_completer(&mut self, _completer: Completer) {}
+
+    /// Decide whether output is shown on a terminal, so that colour can be used.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+/// Read lines from the terminal with linenoise, which provides line editing and a history that
+/// can be saved to a file.
+#[derive(Debug, Default)]
+pub struct LinenoiseSource {
+    /// The completer that was last set.
+    completer: Option<Completer>,
+}
+
+impl LinenoiseSource {
+    /// Make a new linenoise source.
+    pub fn new() -> Self {
+        LinenoiseSource { completer: None }
+    }
+
+    /// Read history from a file.  A missing file is not an error, since there is no history
+    /// the first time.
+    pub fn load_history(&mut self, path: &str) {
+        if let Ok(path) = CString::new(path) {
+            unsafe {
+                linenoise::ffi::linenoiseHistoryLoad(path.as_ptr());
+            }
+        }
+    }
+
+    /// Save the history to a file.  This returns false if the file could not be written.
+    pub fn save_history(&mut self, path: &str) -> bool {
+        match CString::new(path) {
+            Ok(path) => unsafe { linenoise::ffi::linenoiseHistorySave(path.as_ptr()) == 0 },
+            Err(_) => false,
+        }
+    }
+}
+
+impl LineSource for LinenoiseSource {
+    fn read_line(&mut self, prompt: &str) -> Option<String> {
+        READING.with(|reading| *reading.borrow_mut() = self.completer.take());
+        let line = linenoise::input(prompt);
+        self.completer = READING.with(|reading| reading.borrow_mut().take());
+        line
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        // An entry with a nul character cannot be given to linenoise, so it is not kept.
+        if let Ok(entry) = CString::new(entry) {
+            unsafe {
+                linenoise::ffi::linenoiseHistoryAdd(entry.as_ptr());
+            }
+        }
+    }
+
+    fn history(&self) -> Vec<String> {
+        (0..).map(linenoise::history_line).take_while(Option::is_some).flatten().collect()
+    }
+
+    fn clear_screen(&mut self) {
+        linenoise::clear_screen();
+    }
+
+    fn set_completer(&mut self, completer: Completer) {
+        self.completer = Some(completer);
+        linenoise::set_callback(complete);
+    }
+
+    fn is_terminal(&self) -> bool {
+        io::stdout().is_terminal()
+    }
+}
+
+/// Read lines from a stream without line editing.  Prompts are not shown, and the history is
+/// kept in memory.
+pub struct StreamSource<R: BufRead> {
+    /// The stream to read.
+    input: R,
+    /// The entries read so far.
+    history: Vec<String>,
+}
+
+impl StreamSource<io::BufReader<io::Stdin>> {
+    /// Make a new source that reads standard input.
+    pub fn stdin() -> Self {
+        StreamSource::new(io::BufReader::new(io::stdin()))
+    }
+}
+
+impl<R: BufRead> StreamSource<R> {
+    /// Make a new source that reads the given stream.
+    pub fn new(input: R) -> Self {
+        StreamSource {
+            input,
+            history: vec![],
+        }
+    }
+}
+
+impl<R: BufRead> LineSource for StreamSource<R> {
+    fn read_line(&mut self, _prompt: &str) -> Option<String> {
+        let mut line = String::new();
+        match self.input.read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(line)
+            }
+        }
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        self.history.push(entry.to_string());
+    }
+
+    fn history(&self) -> Vec<String> {
+        self.history.clone()
+    }
+}
+
+/// Supply lines given in advance, as for a test.  The prompts asked for are recorded.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedSource {
+    /// The lines not yet read.
+    lines: VecDeque<String>,
+    /// The prompt shown for every line read, including a read at the end of the lines.
+    prompts: Vec<String>,
+    /// The entries read so far.
+    history: Vec<String>,
+    /// The completer that was last set.
+    completer: Option<Completer>,
+}
+
+impl ScriptedSource {
+    /// Make a new source that supplies the given lines, in order.
+    pub fn new<S: AsRef<str>>(lines: &[S]) -> Self {
+        ScriptedSource {
+            lines: lines.iter().map(|line| line.as_ref().to_string()).collect(),
+            prompts: vec![],
+            history: vec![],
+            completer: None,
+        }
+    }
+
+    /// Get the prompts shown so far.
+    pub fn prompts(&self) -> &[String] {
+        &self.prompts
+    }
+
+    /// Get the completer that was last set, if any.
+    pub fn completer(&self) -> Option<&Completer> {
+        self.completer.as_ref()
+    }
+}
+
+impl LineSource for ScriptedSource {
+    fn read_line(&mut self, prompt: &str) -> Option<String> {
+        self.prompts.push(prompt.to_string());
+        self.lines.pop_front()
+    }
+
+    fn add_history(&mut self, entry: &str) {
+        self.history.push(entry.to_string());
+    }
+
+    fn history(&self) -> Vec<String> {
+        self.history.clone()
+    }
+
+    fn set_completer(&mut self, completer: Completer) {
+        self.completer = Some(completer);
+    }
+}
@@ -37,4 +37,14 @@ pub mod util;			// Generic utility functions.
 pub mod prelude;		// Common definitions for programs that want to use relision.
 pub mod platform;		// Platform-specific functions and definitions.
 pub mod repl;			// Implementation of the REPL for relision.
+pub mod input;			// Sources of input lines for the REPL.
+pub mod commands;		// Colon commands for the REPL.
+pub mod session;		// The state of a REPL session.
+pub mod completion;		// Complete partial lines in the REPL.
+pub mod loader;			// Load ELI files.
 pub mod terms;			// Implementation of terms.
+pub mod diagnostics;	// Errors and warnings with source context.
+pub mod error;			// The errors reported by the library.
+
+// The error type is used everywhere, so make it available directly.
+pub use error::{Error, Result};
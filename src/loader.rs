@@ -0,0 +1,200 @@
+//! Load ELI files into a universe.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+A loader reads ELI files and performs their statements in a universe.  A file can load other
+files with the `load` statement.  A relative path is looked for first next to the file that
+loads it (or in the current directory, for input that is not from a file), and then in each
+directory of the include path in turn.  If the path has no extension, `.eli` is also tried.
+
+The loader keeps the files it is in the middle of loading, so a file that loads itself, directly
+or through other files, is reported instead of loaded again.  Loading the same file twice one
+after the other is fine.
+*/
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use diagnostics::{Diagnostic, Sources};
+use error::{Error, Result};
+use terms::{EliParser, Locus, Statement, Term, Universe};
+
+/// The extension tried when a path to load has none.
+const EXTENSION: &str = "eli";
+
+/// Read ELI files and perform their statements.
+#[derive(Clone, Debug, Default)]
+pub struct Loader {
+    /// The directories searched for files, after the directory of the loading file.
+    path: Vec<PathBuf>,
+    /// The files being loaded, outermost first.
+    active: Vec<PathBuf>,
+}
+
+impl Loader {
+    /// Make a new loader that searches the given directories.
+    pub fn new(path: Vec<PathBuf>) -> Self {
+        Loader {
+            path,
+            active: vec![],
+        }
+    }
+
+    /// Get the directories searched for files.
+    pub fn path(&self) -> &[PathBuf] {
+        &self.path
+    }
+
+    /// Find a file to load.  The directory `base` is searched first, and then the include
+    /// path.  An absolute path is only checked.
+    ///
+    ///   * `name`: The path given to load.
+    ///   * `base`: The directory of the file doing the loading, or `None` for the current
+    ///     directory.
+    ///
+    pub fn find(&self, name: &str, base: Option<&Path>) -> Option<PathBuf> {
+        let name = Path::new(name);
+        let mut dirs: Vec<&Path> = vec![];
+        if !name.is_absolute() {
+            dirs.push(base.unwrap_or_else(|| Path::new("")));
+            dirs.extend(self.path.iter().map(|dir| dir.as_path()));
+        } else {
+            dirs.push(Path::new(""));
+        }
+        for dir in dirs {
+            let candidate = dir.join(name);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+            if candidate.extension().is_none() {
+                let candidate = candidate.with_extension(EXTENSION);
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+
+    /// Load a file and perform its statements.  Each term is rewritten and the result given
+    /// to `each`.  The text of the file is added to `sources` so diagnostics can quote it.
+    ///
+    ///   * `universe`: The universe in which to perform the statements.
+    ///   * `sources`: The source text for diagnostics.
+    ///   * `name`: The path of the file.
+    ///   * `from`: Where the load was requested, or `Locus::Internal`.
+    ///   * `width`: The number of characters to mark at `from` in diagnostics.
+    ///   * `each`: Called with the universe and the result of each term.
+    ///
+    pub fn load(&mut self, universe: &mut Universe, sources: &mut Sources, name: &str,
+        from: Locus, width: u32, each: &mut dyn FnMut(&mut Universe, &Arc<Term>))
+        -> Result<()> {
+        let base = match from {
+            Locus::File(ref file, _, _) => Path::new(file).parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map(Path::to_path_buf),
+            _ => None,
+        };
+        let found = match self.find(name, base.as_deref()) {
+            Some(found) => found,
+            None => {
+                let mut dirs = vec![base.map_or(".".to_string(), |dir| show(&dir))];
+                for dir in &self.path {
+                    if !dirs.contains(&show(dir)) {
+                        dirs.push(show(dir));
+                    }
+                }
+                let message = format!("cannot find the file {}", name);
+                let note = format!("looked in {}", dirs.join(", "));
+                return Err(error(&message, &from, width, "not found", &note));
+            }
+        };
+        let canonical = fs::canonicalize(&found).unwrap_or_else(|_| found.clone());
+        if let Some(start) = self.active.iter().position(|file| *file == canonical) {
+            let mut chain: Vec<String> = self.active[start..].iter().map(|file| show(file))
+                .collect();
+            chain.push(show(&canonical));
+            let message = format!("the file {} loads itself", show(&found));
+            let note = format!("the files load each other in a cycle: {}", chain.join(" -> "));
+            return Err(error(&message, &from, width, "loaded again here", &note));
+        }
+        let mut text = String::new();
+        File::open(&found)?.read_to_string(&mut text)?;
+        let file = show(&found);
+        sources.add_file(&file, &text);
+        self.active.push(canonical);
+        let result = self.run(universe, sources, &text, Locus::File(file, 1, 1), each);
+        self.active.pop();
+        result
+    }
+
+    /// Perform the statements in some text.  Each term is rewritten and the result given to
+    /// `each`.  The first error stops the rest of the text from being performed.
+    ///
+    ///   * `universe`: The universe in which to perform the statements.
+    ///   * `sources`: The source text for diagnostics.
+    ///   * `text`: The text to perform.
+    ///   * `start`: Where the text starts.
+    ///   * `each`: Called with the universe and the result of each term.
+    ///
+    pub fn run(&mut self, universe: &mut Universe, sources: &mut Sources, text: &str,
+        start: Locus, each: &mut dyn FnMut(&mut Universe, &Arc<Term>)) -> Result<()> {
+        let mut parser = EliParser::new(text, start);
+        while let Some(statement) = parser.next_statement(universe.factory())? {
+            self.perform(universe, sources, statement, each)?;
+        }
+        Ok(())
+    }
+
+    /// Perform one statement.  A load statement loads the file, and a term is rewritten and
+    /// the result given to `each`.
+    ///
+    ///   * `universe`: The universe in which to perform the statement.
+    ///   * `sources`: The source text for diagnostics.
+    ///   * `statement`: The statement to perform.
+    ///   * `each`: Called with the universe and the result of each term.
+    ///
+    pub fn perform(&mut self, universe: &mut Universe, sources: &mut Sources,
+        statement: Statement, each: &mut dyn FnMut(&mut Universe, &Arc<Term>)) -> Result<()> {
+        if let Statement::Load(locus, name, width) = statement {
+            self.load(universe, sources, &name, locus, width, each)
+        } else {
+            if let Some(term) = universe.execute(statement)? {
+                let result = universe.rewrite(&term)?;
+                each(universe, &result);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Show a path in a diagnostic.
+fn show(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// Make an error about a load, pointing at what requested it if there is anything.
+fn error(message: &str, from: &Locus, width: u32, label: &str, note: &str) -> Error {
+    let diagnostic = Diagnostic::error(message).with_note(note);
+    Error::Parse(Box::new(match *from {
+        Locus::Internal => diagnostic,
+        _ => diagnostic.with_primary(from.clone(), width, label),
+    }))
+}
@@ -22,9 +22,14 @@ extern crate getopts;       // Use an external crate to process command line opt
 
 use getopts::Options;
 use std::env;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use std::process;
 
 // Get the module implementing the REPL functions.
 use relision::repl;
+use relision::input::{LinenoiseSource, StreamSource};
+use relision::loader::Loader;
 
 /// Print the command line help.  First print the prototype for using the
 /// command, and then print help about using the switches.
@@ -33,48 +38,16 @@ use relision::repl;
 fn print_usage(progname: &str, switches: Options) {
     let prototype = format!("Usage: {} [switches...] [elision files...]", progname);
     print!("{}", switches.usage(&prototype));
+    println!("\nWith -e, or when standard input is not a terminal, the files are loaded, the");
+    println!("expressions (or else standard input) are evaluated, and the exit status is 0 on");
+    println!("success, 1 if there was an error in the input, or 2 for a bad command line.");
+    println!("This batch mode prints only results and errors, so --no-banner and --quiet");
+    println!("change nothing in it; -I applies in both modes.  Colon commands cannot be used");
+    println!("in batch mode: use the load and set statements instead.");
 }
 
 /// Entry point when run from the prompt.
 fn main() {
-    // Go and get the locus stuff.
-    use relision::terms::Locus;
-    let locus1 = Locus::Internal;
-    let locus2 = Locus::Console(9, 21);
-    let locus3 = Locus::File("brenda.eli".to_string(), 9, 21);
-    let locus4 = Locus::File("brenda.eli".to_string(), 9, 21);
-    println!("locus1: {}\nlocus2: {}\nlocus3: {}\nlocus4: {}\n", locus1, locus2, locus3, locus4);
-    println!("{} == {} -> {}", locus3, locus4, locus3 == locus4);
-    println!("{} == {} -> {}", locus2, locus3, locus2 == locus3);
-
-    // use std::sync::Arc;
-    // use relision::terms::Term;
-    use relision::terms::TermFactory;
-    use relision::terms::{TermWriter, EliWriter};
-    let fact = TermFactory::new();
-    let eli = EliWriter::new();
-    let t = fact.get_root();
-    println!("{}\n    {:?}", t, t);
-    let u = fact.new_string(Locus::Internal, "Mister \nPickles".to_string());
-    println!("{}\n    {:?}", u, u);
-    let v = fact.new_variable(Locus::Internal, &fact.get_any(), "viv\0ian".to_string(),
-        &fact.new_boolean(true));
-    println!("{}\n    {:?}", v, v);
-    let m = fact.new_static_map(Locus::Internal, &v, &u);
-    println!("{}\n    {:?}", m, m);
-    let p = fact.new_static_product(Locus::Internal, &fact.get_string(), &fact.get_symbol());
-    println!("{}\n    {:?}", p, p);
-    let l = fact.new_lambda(Locus::Internal, &v, &m, &fact.new_boolean(true));
-    println!("{}\n    {:?}", l, l);
-    println!("{}\n    {:?}", fact.get_type(&l), fact.get_type(&l));
-    println!("");
-
-    // Now print using the ELI formatter.
-    let testy = fact.new_string(Locus::Internal, "|\u{a}\u{d}|\0|\t|\n|\r|\"|\'|?|\\|`|".to_string());
-    eli.println(&fact, &u);
-    eli.println(&fact, &testy);
-    eli.println(&fact, &l);
-
     // Get the command line arguments.
     let args: Vec<String> = env::args().collect();
     let me = args[0].clone();
@@ -82,15 +55,23 @@ fn main() {
     // Specify the switches this wrapper takes.
     let mut switches = Options::new();
     switches.optflag("h", "help", "Print this command line help.");
+    switches.optmulti("I", "include", "Search this directory for files to load, before the \
+        directories in RELISION_PATH.", "DIR");
+    switches.optmulti("e", "eval", "Evaluate this expression and exit.  May be repeated.",
+        "EXPR");
+    switches.optflag("", "no-banner", "Do not print the banner.  Batch mode never prints it.");
+    switches.optflag("q", "quiet", "Print only results and errors, as batch mode always does.");
+    switches.optflag("i", "interactive", "Run the REPL even when standard input is not a \
+        terminal.");
 
     // Now process all command line switches.  The "tail" removes the program
     // name.
     let matches = match switches.parse(&args[1..]) {
         Ok(mat) => mat,
         Err(fail) => {
-            println!("ERROR parsing command line arguments:");
-            println!("  {}", fail.to_string());
-            return;
+            eprintln!("ERROR parsing command line arguments:");
+            eprintln!("  {}", fail);
+            process::exit(repl::EXIT_USAGE);
         }
     };
     if matches.opt_present("h") {
@@ -98,13 +79,61 @@ fn main() {
         return;
     }
 
+    // Files are searched for in the directories given on the command line, and then in the
+    // directories given by the environment.
+    let mut path: Vec<PathBuf> = matches.opt_strs("I").iter().map(PathBuf::from).collect();
+    path.extend(relision::platform::get_include_path());
+    let loader = Loader::new(path);
+
+    // Without a terminal to interact with, or with expressions to evaluate, run in batch mode
+    // and exit with a code that reports whether there were errors.  Only results and errors
+    // are printed in batch mode, so the output can be used by other programs.
+    let expressions = matches.opt_strs("e");
+    let terminal = io::stdin().is_terminal();
+    if !expressions.is_empty() || !(terminal || matches.opt_present("i")) {
+        let mut script = None;
+        if expressions.is_empty() {
+            let mut text = String::new();
+            if let Err(err) = io::stdin().read_to_string(&mut text) {
+                eprintln!("ERROR reading standard input: {}", err);
+                process::exit(repl::EXIT_FAILURE);
+            }
+            script = Some(text);
+        }
+        process::exit(repl::batch(loader, &matches.free, &expressions, script.as_deref()));
+    }
+
     // Print the banner.
-    repl::banner();
-    println!("Running on {}.", relision::platform::get_platform());
-    let config_dir = relision::platform::get_config_dir();
-    println!("Configuration stored at: {}.", config_dir);
+    let quiet = matches.opt_present("q");
+    if !quiet && !matches.opt_present("no-banner") {
+        repl::banner();
+    }
+    if !quiet {
+        println!("Running on {}.", relision::platform::get_platform());
+    }
+
+    // Without a terminal there is no line editing, and the history is not kept.
+    if !terminal {
+        let mut source = StreamSource::stdin();
+        repl::repl(repl::define_commands(), loader, &matches.free, &mut source, &mut io::stdout());
+        return;
+    }
 
-    // Now run the REPL.
-    let history_filename = config_dir + ("/repl.history");
-    repl::backed_repl(&history_filename);
+    // Now run the REPL.  If there is nowhere to keep the history, run without it.
+    match relision::platform::get_config_dir() {
+        Ok(config_dir) => {
+            if !quiet {
+                println!("Configuration stored at: {}.", config_dir);
+            }
+            let history_filename = config_dir + ("/repl.history");
+            repl::backed_repl(&history_filename, loader, &matches.free);
+        }
+        Err(err) => {
+            println!("WARNING: {}", err);
+            println!("History will not be saved.");
+            let mut source = LinenoiseSource::new();
+            repl::repl(repl::define_commands(), loader, &matches.free, &mut source,
+                &mut io::stdout());
+        }
+    }
 }
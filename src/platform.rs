@@ -18,6 +18,8 @@
 //! modified, or distributed except according to those terms.
 
 use std::env;
+use std::path::PathBuf;
+use error::{Error, Result};
 
 /// Get the name of the platform for which this version of relision was
 /// compiled.
@@ -71,7 +73,10 @@ pub fn get_platform() -> &'static str {
 ///
 ///   * `$XDG_CONFIG_HOME/relision`
 ///   * `$HOME/.config/relision`
-pub fn get_config_dir() -> String {
+///
+/// # Errors
+/// If none of the locations can be determined, an `Error::Config` is returned.
+pub fn get_config_dir() -> Result<String> {
     if cfg!(target_os = "macos") {
         match env::home_dir() {
             Some(value) => {
@@ -81,20 +86,20 @@ pub fn get_config_dir() -> String {
                 result.push("relision");
                 match result.into_os_string().into_string() {
                     Ok(rawpath) => {
-                        return rawpath;
+                        return Ok(rawpath);
                     }
                     Err(_) => {}
                 };
             }
             None => {}
         };
-        panic!("Cannot locate configuration files for relision.  Please set HOME.");
+        Err(no_config_dir())
     } else if cfg!(target_os = "windows") {
         match env::var_os("LOCALAPPDATA") {
             Some(value) => {
                 match value.into_string() {
                     Ok(prefix) => {
-                        return prefix + "\\relision";
+                        return Ok(prefix + "\\relision");
                     }
                     Err(_) => {}
                 };
@@ -105,7 +110,7 @@ pub fn get_config_dir() -> String {
             Some(value) => {
                 match value.into_string() {
                     Ok(prefix) => {
-                        return prefix + "\\AppData\\Local\\relision";
+                        return Ok(prefix + "\\AppData\\Local\\relision");
                     }
                     Err(_) => (),
                 };
@@ -120,14 +125,14 @@ pub fn get_config_dir() -> String {
                 result.push("relision");
                 match result.into_os_string().into_string() {
                     Ok(res) => {
-                        return res;
+                        return Ok(res);
                     }
                     Err(_) => {}
                 };
             }
             None => {}
         };
-        panic!("Cannot locate configuration files for relision.  Please set HOME.");
+        Err(no_config_dir())
     } else {
         match env::var_os("XDG_CONFIG_HOME") {
             Some(value) => {
@@ -135,7 +140,7 @@ pub fn get_config_dir() -> String {
                 result.push("/relision");
                 match result.into_string() {
                     Ok(rawpath) => {
-                        return rawpath;
+                        return Ok(rawpath);
                     }
                     Err(_) => {}
                 };
@@ -149,13 +154,30 @@ pub fn get_config_dir() -> String {
                 result.push("relision");
                 match result.into_os_string().into_string() {
                     Ok(rawpath) => {
-                        return rawpath;
+                        return Ok(rawpath);
                     }
                     Err(_) => {}
                 };
             }
             None => {}
         };
-        panic!("Cannot locate configuration files for relision.  Please set HOME.");
+        Err(no_config_dir())
+    }
+}
+
+/// Get the directories to search for files to load, from the `RELISION_PATH` environment
+/// variable.  The directories are separated as in `PATH` on this platform (by `:`, or by `;` on
+/// Windows).  Empty entries are ignored, and if the variable is not set the list is empty.
+pub fn get_include_path() -> Vec<PathBuf> {
+    match env::var_os("RELISION_PATH") {
+        Some(value) => {
+            env::split_paths(&value).filter(|dir| !dir.as_os_str().is_empty()).collect()
+        }
+        None => vec![],
     }
 }
+
+/// Make the error reported when the configuration directory cannot be found.
+fn no_config_dir() -> Error {
+    Error::Config("Cannot locate configuration files for relision.  Please set HOME.".to_string())
+}
@@ -17,164 +17,467 @@
 //! that is part of this distribution.  This file may not be copied,
 //! modified, or distributed except according to those terms.
 
-extern crate linenoise;
 extern crate num;
 extern crate getopts;
 
-/// The return result from a callback.  Errors are returned as a string in an `Err` result, while
-/// success is indicated by `Ok("".to_owned())`.
-pub type CallbackResult = Result<String, String>;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Arc;
+use completion::Completer;
+use diagnostics::{Diagnostic, Sources};
+use input::{LineSource, LinenoiseSource};
+use loader::Loader;
+use session::{diagnose, report, show, Session};
+use terms::{EliParser, Locus, Term, Universe};
 
-/// A callback is a function mapping a vector of strings to a return value.  The argument to the
-/// function is the vector of text arguments given at invocation.  The result indicates success or
-/// an error.
-pub type Callback = Box<Fn(Vec<&str>) -> CallbackResult>;
-
-/// Struct to hold a colon command definition.
-pub struct ColonCommand {
-    /// The name of the command, matched by first unique prefix.
-    pub name: &'static str,
-    /// The action to perform when the command is detected.
-    pub action: Callback,
-}
+pub use commands::{Argument, Callback, CallbackResult, ColonCommand, CommandRegistry};
 
 /// Obtain the supported colon commands, each mapped to a closure that
 /// executes the command.
-pub fn define_commands() -> Vec<ColonCommand> {
-    // TODO This should really be a map.  Need to change that at some point.
-    vec![ColonCommand {
-             name: "clear",
-             action: Box::new(|_| -> CallbackResult {
-                 linenoise::clear_screen();
-                 Ok("".to_string())
-             }),
-         },
-         ColonCommand {
-             name: "history",
-             action: Box::new(|_| -> CallbackResult {
-                 let mut index = 0;
-                 loop {
-                     match linenoise::history_line(index) {
-                         None => {
-                             break;
-                         }
-                         Some(line) => {
-                             println!("{}: {}", index, line);
-                         }
-                     };
-                     index = index + 1;
-                 }
-                 Ok("".to_string())
-             }),
-         },
-         ColonCommand {
-             // This command is special.  It is trapped by the main loop and
-             // causes the REPL to terminate.
-             name: "quit",
-             action: Box::new(|_| -> CallbackResult { Ok("".to_string()) }),
-         }]
+pub fn define_commands() -> CommandRegistry {
+    let mut commands = CommandRegistry::new();
+    commands.add(ColonCommand::new("clear", ":clear", "Clear the screen.",
+        Box::new(|session, _| -> CallbackResult {
+            session.source.clear_screen();
+            Ok("".to_string())
+        })));
+    commands.add(ColonCommand::new("history", ":history", "List the entries read so far.",
+        Box::new(|session, _| -> CallbackResult {
+            let mut text = String::new();
+            for (index, entry) in session.source.history().iter().enumerate() {
+                text.push_str(&format!("{}: {}\n", index, entry));
+            }
+            Ok(text)
+        })));
+    commands.add(ColonCommand::new("help", ":help [COMMAND]",
+        "Describe every command, or one command in full.", Box::new(help))
+        .with_aliases(&["?"])
+        .with_arity(0, Some(1)));
+    commands.add(ColonCommand::new("set", ":set [NAME VALUE]",
+        "List the writer options and settings, or change one.", Box::new(set))
+        .with_arity(0, Some(2)));
+    commands.add(ColonCommand::new("load", ":load PATH", "Load a file of declarations and terms.",
+        Box::new(|session, arguments| -> CallbackResult {
+            let from = Locus::Console(session.line, arguments[1].offset as u32 + 1);
+            session.load(&arguments[1].text, from, arguments[1].width as u32);
+            Ok("".to_string())
+        }))
+        .with_arity(1, Some(1)));
+    commands.add(ColonCommand::new("type", ":type TERM", "Show the type of a term.",
+        Box::new(type_of))
+        .with_term(0));
+    commands.add(ColonCommand::new("rules", ":rules", "List the rules in the order they are tried.",
+        Box::new(rules)));
+    commands.add(ColonCommand::new("results", ":results", "List the numbered results so far.",
+        Box::new(|session, _| -> CallbackResult {
+            let mut text = String::new();
+            for (index, term) in session.results.iter().enumerate() {
+                text.push_str(&format!("$_{} = {}\n", index + 1,
+                    session.universe.display(term)));
+            }
+            Ok(text)
+        })));
+    commands.add(ColonCommand::new("quit", ":quit", "Leave the REPL, after asking.",
+        Box::new(|session, _| -> CallbackResult {
+            if let Some(answer) = session.source.read_line("Really quit? (y/n) ") {
+                session.quit = answer.starts_with('y') || answer.starts_with('Y');
+            }
+            Ok("".to_string())
+        }))
+        .with_aliases(&["exit"]));
+    commands
+}
+
+/// The exit code when every file, expression, and script was evaluated without error.
+pub const EXIT_SUCCESS: i32 = 0;
+
+/// The exit code when the input could not be loaded, parsed, or rewritten.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// The exit code when the command line is not valid.
+pub const EXIT_USAGE: i32 = 2;
+
+/// The name diagnostics use for the files named on the command line.
+const COMMAND_LINE: &str = "<command line>";
+
+/// Evaluate input without interaction, for use in scripts.  The files are loaded first, then
+/// each expression is evaluated, and then the script.  Results are printed to standard output
+/// and diagnostics to standard error.  Evaluation stops at the first error.  There is no
+/// session, so colon commands are errors; the `load` and `set` statements do the same work.
+///
+///   * `loader`: The loader used for files.
+///   * `files`: Files to load, in order.
+///   * `expressions`: Entries to evaluate, in order.  Each is numbered as a console line.
+///   * `script`: The text of a script read from standard input, if there is one.
+///
+/// The exit code for the process is returned: `EXIT_SUCCESS`, or `EXIT_FAILURE` if there was an
+/// error.
+pub fn batch(mut loader: Loader, files: &[String], expressions: &[String],
+    script: Option<&str>) -> i32 {
+    let mut sources = Sources::new();
+    let mut universe = Universe::new();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let colour = io::stderr().is_terminal();
+    let show = &mut |universe: &mut Universe, term: &Arc<Term>| show(&mut out, universe, term);
+    for (file, from) in files.iter().zip(command_line(&mut sources, files)) {
+        let width = file.chars().count() as u32;
+        if let Err(err) = loader.load(&mut universe, &mut sources, file, from, width, show) {
+            report(&mut io::stderr(), colour, &err.into_diagnostic(), &sources);
+            return EXIT_FAILURE;
+        }
+    }
+    let mut line: u32 = 1;
+    for expression in expressions {
+        sources.add_console(line, expression);
+        let start = Locus::Console(line, 1);
+        if let Some((index, column, name)) = find_command(expression) {
+            let locus = Locus::Console(line + index, column);
+            report(&mut io::stderr(), colour, &batch_command(name, locus), &sources);
+            return EXIT_FAILURE;
+        }
+        line += expression.lines().count().max(1) as u32;
+        if let Err(err) = loader.run(&mut universe, &mut sources, expression, start.clone(),
+            show) {
+            report(&mut io::stderr(), colour, &diagnose(err, expression, start), &sources);
+            return EXIT_FAILURE;
+        }
+    }
+    if let Some(script) = script {
+        let name = "<stdin>";
+        sources.add_file(name, script);
+        let start = Locus::File(name.to_string(), 1, 1);
+        if let Some((index, column, command)) = find_command(script) {
+            let locus = Locus::File(name.to_string(), index + 1, column);
+            report(&mut io::stderr(), colour, &batch_command(command, locus), &sources);
+            return EXIT_FAILURE;
+        }
+        if let Err(err) = loader.run(&mut universe, &mut sources, script, start, show) {
+            report(&mut io::stderr(), colour, &err.into_diagnostic(), &sources);
+            return EXIT_FAILURE;
+        }
+    }
+    EXIT_SUCCESS
+}
+
+/// Find the first colon command in batch input: a line that starts with a colon where the REPL
+/// would start a new entry.  The number of lines before it, its column, and the command as
+/// given are returned.
+fn find_command(text: &str) -> Option<(u32, u32, &str)> {
+    let mut entry = String::new();
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if entry.is_empty() && trimmed.starts_with(':') {
+            let column = line.chars().count() - trimmed.chars().count() + 1;
+            let command = trimmed.split_whitespace().next().unwrap_or(":");
+            return Some((index as u32, column as u32, command));
+        }
+        if !entry.is_empty() {
+            entry.push('\n');
+        }
+        entry.push_str(line);
+        if !is_incomplete(&entry) {
+            entry.clear();
+        }
+    }
+    None
+}
+
+/// Make the diagnostic for a colon command in batch input.
+///
+///   * `command`: The command as given, with the colon.
+///   * `locus`: Where the command starts.
+///
+fn batch_command(command: &str, locus: Locus) -> Diagnostic {
+    Diagnostic::error(&format!("the command {} cannot be used in batch mode", command))
+        .with_primary(locus, command.chars().count() as u32, "colon command")
+        .with_note("use a load or set statement instead, or -i to run the REPL on the input")
+}
+
+/// Keep the names of the files given on the command line in the sources, one to a line, so
+/// that diagnostics can quote them, and get where each is named.
+fn command_line(sources: &mut Sources, files: &[String]) -> Vec<Locus> {
+    sources.add_file(COMMAND_LINE, &files.join("\n"));
+    (1..).take(files.len()).map(|line| Locus::File(COMMAND_LINE.to_string(), line, 1)).collect()
 }
 
 /// Create a REPL backed by a history file.  Prior history is read at start, and then saved at the
 /// end.  The argument must be the full path to the file storing the history.
 ///
 ///   * `history_filename`: The path to the file where history is stored.
+///   * `loader`: The loader used for files.
+///   * `files`: Files to load before the first prompt.
 ///
 /// Note: The history file must be both readable and writeable.
-pub fn backed_repl(history_filename: &str) {
-    linenoise::history_load(&history_filename);
-    repl(define_commands());
-    linenoise::history_save(&history_filename);
+pub fn backed_repl(history_filename: &str, loader: Loader, files: &[String]) {
+    let mut source = LinenoiseSource::new();
+    source.load_history(history_filename);
+    repl(define_commands(), loader, files, &mut source, &mut io::stdout());
+    source.save_history(history_filename);
 }
 
 /// Implementation of an interactive REPL.
 ///
 ///   * `commands`: An array defining all colon commands.
+///   * `loader`: The loader used for files.
+///   * `files`: Files to load, in order, before the first prompt.
+///   * `source`: Where to read lines and keep the history.
+///   * `out`: Where to write results and diagnostics.
 ///
-pub fn repl(commands: Vec<ColonCommand>) {
-    'repl: loop {
-        let val = linenoise::input("e> ");
-        match val {
-            None => {
-                break;
-            }
-            Some(input) => {
-                // Accumulate history but ignore empty lines.
-                let input = input.trim();
-                if input == "" {
-                    continue 'repl;
+pub fn repl(commands: CommandRegistry, loader: Loader, files: &[String],
+    source: &mut dyn LineSource, out: &mut dyn Write) {
+    let mut session = Session::new(commands, loader, source, out);
+    for (file, from) in files.iter().zip(command_line(&mut session.sources, files)) {
+        session.load(file, from, file.chars().count() as u32);
+    }
+    while !session.quit {
+        // The roots and operators change as entries are evaluated, so the completer is made
+        // afresh for each entry.
+        let completer = Completer::new(&session);
+        session.source.set_completer(completer);
+        let input = match session.source.read_line("e> ") {
+            None => break,
+            Some(input) => input,
+        };
+        // Accumulate history but ignore empty lines.
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        session.line += 1;
+        session.sources.add_console(session.line, input);
+
+        // Keep reading while the entry is incomplete.  Colon commands are always a single line.
+        // Each line is kept under its own number, so the entry as a whole starts at the locus of
+        // its first line.
+        let start = Locus::Console(session.line, 1);
+        let mut entry = input.to_string();
+        while !entry.starts_with(':') && is_incomplete(&entry) {
+            match session.source.read_line(".. ") {
+                None => break,
+                Some(more) => {
+                    let more = more.trim_end();
+                    session.line += 1;
+                    session.sources.add_console(session.line, more);
+                    entry.push('\n');
+                    entry.push_str(more);
                 }
-                linenoise::history_add(&input);
-
-                // Check for, and process, any colon commands.  We allow commands
-                // to be abbreviated to just the first few characters, but this
-                // must be unambiguous.
-                if input.starts_with(":") {
-                    // Found a potential colon command, so now process it.
-                    // TODO Right now we split at spaces, but once the parser is ready we should
-                    // use it, because this might split within strings, etc.
-                    let arguments: Vec<&str> = input.split(' ').collect();
-                    let mut possible: Option<&ColonCommand> = None;
-                    let mut found = false;
-                    let command = arguments[0].clone();
-                    let command = &command[1..];
-                    for cmd in &commands {
-                        if cmd.name.starts_with(command) {
-                            // Found a possible answer.
-                            match possible {
-                                None => {
-                                    possible = Some(cmd);
-                                    found = true;
-                                }
-                                Some(other) => {
-                                    println!("ERROR: The command :{} is ambiguous.  It might be \
-                                              :{} or :{}.",
-                                             command,
-                                             cmd.name,
-                                             other.name);
-                                    break;
-                                }
-                            }
-                        }
-                    } // Iterate over commands.
-                    if !found {
-                        println!("ERROR: The command :{} was not recognized.", command);
-                    } else {
-                        // We found the command (possibly) and we need to execute it, passing along
-                        // all the other arguments.  The return will be either Ok (if all went well),
-                        // or Err (if something went wrong), and we need to print the error message.
-                        match possible {
-                            None => {}
-                            Some(other) => {
-                                // Terminate on the magic quit command.
-                                if other.name == "quit" {
-                                    let answer = linenoise::input("Really quit? (y/n) ");
-                                    match answer {
-                                        Some(input) => {
-                                            if input.starts_with("y") || input.starts_with("Y") {
-                                                break 'repl;
-                                            }
-                                        }
-                                        None => {}
-                                    }
-                                }
-                                match (&other.action)(arguments) {
-                                    Ok(_) => {}
-                                    Err(msg) => {
-                                        println!("ERROR: {}", msg);
-                                    }
-                                }
-                            }
+            }
+        }
+        session.source.add_history(&entry);
+        if entry.starts_with(':') {
+            command(&mut session, &entry, start);
+        } else {
+            session.evaluate(&entry, start);
+        }
+    }
+    let _ = writeln!(session.out, "Terminating REPL.");
+}
+
+/// Find and run a colon command.  We allow commands to be abbreviated to just the first few
+/// characters, but this must be unambiguous.  Failures are reported at the command.
+///
+///   * `session`: The session to run the command in.
+///   * `input`: The command line.
+///   * `start`: Where the command line starts.
+///
+fn command(session: &mut Session, input: &str, start: Locus) {
+    // The command name is found first, since it decides whether the line ends in a term.
+    let first = input.split_whitespace().next().unwrap_or(":");
+    let name = &first[1..];
+    let width = first.chars().count() as u32;
+    let commands = session.commands.clone();
+    let command = match commands.lookup(name) {
+        Ok(command) => command,
+        Err(candidates) => {
+            session.report(&failure(&unknown(name, &candidates), start, width));
+            return;
+        }
+    };
+    let arguments = match command.split(input) {
+        Ok(arguments) => arguments,
+        Err(err) => {
+            let locus = Locus::Console(session.line, err.offset as u32 + 1);
+            session.report(&failure(&err.message, locus, err.width as u32));
+            return;
+        }
+    };
+    let result = if command.accepts(arguments.len() - 1) {
+        (command.action)(session, &arguments)
+    } else {
+        Err(format!("usage: {}", command.usage))
+    };
+    match result {
+        Ok(text) => {
+            let _ = write!(session.out, "{}", text);
+        }
+        Err(msg) => session.report(&failure(&msg, start, width)),
+    }
+}
+
+/// Make the diagnostic for a failed command.  The first line of the message is the message of
+/// the diagnostic, and any others are notes.
+fn failure(message: &str, locus: Locus, width: u32) -> Diagnostic {
+    let mut lines = message.lines();
+    let mut diagnostic = Diagnostic::error(lines.next().unwrap_or(""))
+        .with_primary(locus, width, "");
+    for note in lines {
+        diagnostic = diagnostic.with_note(note);
+    }
+    diagnostic
+}
+
+/// Describe a command that could not be found.  The message is on the first line, and a note
+/// listing the candidates, if there are any, is on the second.
+///
+///   * `command`: The command as given, without the colon.
+///   * `candidates`: The names of the commands it might refer to.
+///
+fn unknown(command: &str, candidates: &[&str]) -> String {
+    let names: Vec<String> = candidates.iter().map(|name| format!(":{}", name)).collect();
+    match names.len() {
+        0 => format!("the command :{} was not recognized\nuse :help to list the commands",
+            command),
+        2 => format!("the command :{} is ambiguous\nit might be {} or {}", command, names[0],
+            names[1]),
+        count => format!("the command :{} is ambiguous\nit might be {}, or {}", command,
+            names[..count - 1].join(", "), names[count - 1]),
+    }
+}
+
+/// Describe the commands for the `:help` command, or the one command given in full, with or
+/// without its colon.
+fn help(session: &mut Session, arguments: &[Argument]) -> CallbackResult {
+    match arguments.get(1) {
+        None => Ok(session.commands.help()),
+        Some(name) => {
+            let name = name.text.trim_start_matches(':');
+            session.commands.lookup(name).map(|command| command.help())
+                .map_err(|candidates| unknown(name, &candidates))
+        }
+    }
+}
+
+/// Show the type of a term for the `:type` command.  Errors in the term are reported here, so
+/// that they can point into the term.
+fn type_of(session: &mut Session, arguments: &[Argument]) -> CallbackResult {
+    let locus = Locus::Console(session.line, arguments[1].offset as u32 + 1);
+    let result = EliParser::new(&arguments[1].text, locus)
+        .parse_term(session.universe.factory());
+    match result {
+        Ok(term) => {
+            let typ = session.universe.factory().get_type(&term);
+            Ok(format!("  : {}\n", session.universe.display(&typ)))
+        }
+        Err(err) => {
+            session.report(&err.into_diagnostic());
+            Ok("".to_string())
+        }
+    }
+}
+
+/// List the rules for the `:rules` command, numbered in the order they are tried, with their
+/// rulesets.  Rules whose rulesets are all disabled are marked.
+fn rules(session: &mut Session, _: &[Argument]) -> CallbackResult {
+    let universe = &session.universe;
+    let fact = universe.factory();
+    let mut text = String::new();
+    for (index, rule) in universe.rules().iter().enumerate() {
+        let lambda = fact.new_lambda(Locus::Internal, &rule.pattern, &rule.rewrite, &rule.guard);
+        let enabled = rule.rulesets.iter().any(|name| universe.rules().is_enabled(name));
+        text.push_str(&format!("{}: {} in {}{}\n", index, universe.display(&lambda),
+            rule.rulesets.join(", "), if enabled { "" } else { " (disabled)" }));
+    }
+    Ok(text)
+}
+
+/// Change a writer option or a setting of the universe for the `:set` command.  With no
+/// arguments, list the writer options and settings with their values.
+fn set(session: &mut Session, arguments: &[Argument]) -> CallbackResult {
+    let universe = &mut session.universe;
+    match arguments.len() {
+        1 => {
+            let mut text = String::new();
+            for (name, value, choices) in universe.writer().config().options() {
+                text.push_str(&format!("{} = {}  ({})\n", name, value, choices));
+            }
+            for (name, setting) in universe.settings().iter() {
+                text.push_str(&format!("{} = {}  ({})\n", name, setting.value,
+                    setting.description));
+            }
+            Ok(text)
+        }
+        3 => {
+            let (name, value) = (arguments[1].text.as_str(), arguments[2].text.as_str());
+            let mut config = universe.writer().config().clone();
+            let result = if config.options().iter().any(|&(option, _, _)| option == name) {
+                config.set(name, value).map(|_| universe.writer_mut().set_config(config))
+            } else {
+                universe.settings_mut().set(name, value)
+            };
+            result.map(|_| "".to_string()).map_err(|err| err.to_string())
+        }
+        _ => Err("usage: :set [NAME VALUE]".to_string()),
+    }
+}
+
+/// Decide whether an entry needs more lines.  It does if a parenthesis or brace is unclosed, if
+/// a string literal, quoted name, or block comment is unterminated, or if the last token is an
+/// operator that must be followed by a term.  Closing brackets without a matching opening are
+/// left for the parser to report.
+///
+///   * `text`: The entry read so far.
+///
+pub fn is_incomplete(text: &str) -> bool {
+    let mut depth: i32 = 0;
+    let mut last = ' ';
+    let mut pending = ' ';
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' | '`' => {
+                loop {
+                    match chars.next() {
+                        None => return true,
+                        Some('\\') => {
+                            chars.next();
                         }
+                        Some(end) if end == ch => break,
+                        Some(_) => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&next| next != '\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut star = false;
+                loop {
+                    match chars.next() {
+                        None => return true,
+                        Some('/') if star => break,
+                        Some(next) => star = next == '*',
                     }
-                } else {
-                    // Not a colon command.
-                    println!("  -> {}", input);
                 }
+                continue;
             }
-        } // Match.
-    } // REPL loop.
-    println!("Terminating REPL.");
+            '(' | '{' => depth += 1,
+            ')' | '}' => depth -= 1,
+            _ => {}
+        }
+        if !ch.is_whitespace() {
+            pending = last;
+            last = ch;
+        }
+    }
+    depth > 0 || match (pending, last) {
+        ('=', '>') | ('-', '>') => true,
+        (_, '>') => false,
+        (_, last) => "*:,=".contains(last),
+    }
 }
 
 /// Print the banner for the project.
@@ -0,0 +1,239 @@
+//! Hold the state of a REPL session.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+A session holds everything a REPL works with: the universe, the loader, the text read so far,
+the source of input lines with its history, and where output goes.  Colon commands are given
+the session, so that a command can do anything the REPL itself can, including ending it by
+setting the quit flag.  The writer settings are those of the universe's writer.
+
+Every result shown is numbered, starting from one, and kept.  The variable `$_N` stands for the
+result numbered N, and `$_` for the last result, so later entries can use them.  The variables
+are replaced in the terms of each console entry before they are checked and rewritten, but not
+in the terms of files, which mean the same wherever they are loaded.
+
+The command registry is shared, rather than owned, so that a command can be run while the
+session it is given still holds the registry, as `:help` needs.
+*/
+
+use std::io::Write;
+use std::rc::Rc;
+use std::sync::Arc;
+use commands::CommandRegistry;
+use diagnostics::{Diagnostic, Sources};
+use error::{Error, Result};
+use input::LineSource;
+use loader::Loader;
+use terms::{bind, EliParser, EliWriter, Locus, Statement, Term, TermWriter, Universe};
+
+/// The width results are laid out in, counting the prefix.
+const WIDTH: usize = 80;
+
+/// What is printed before each result.
+const PREFIX: &str = "  -> ";
+
+/// The state of a REPL session.
+pub struct Session<'a> {
+    /// The universe entries are evaluated in.
+    pub universe: Universe,
+    /// The loader used for files.
+    pub loader: Loader,
+    /// The text of every line and file read, so diagnostics can quote it.
+    pub sources: Sources,
+    /// The colon commands.
+    pub commands: Rc<CommandRegistry>,
+    /// Where lines are read, and the history kept.
+    pub source: &'a mut dyn LineSource,
+    /// Where results and diagnostics are written.
+    pub out: &'a mut dyn Write,
+    /// Whether diagnostics use colour.
+    pub colour: bool,
+    /// The number of the last console line read.
+    pub line: u32,
+    /// Whether the REPL should stop after the current entry.
+    pub quit: bool,
+    /// Every result shown, in order.  The first is `$_1`.
+    pub results: Vec<Arc<Term>>,
+}
+
+impl<'a> Session<'a> {
+    /// Make a new session with an empty universe.  Colour is used if the source of lines is a
+    /// terminal.
+    ///
+    ///   * `commands`: The colon commands.
+    ///   * `loader`: The loader used for files.
+    ///   * `source`: Where to read lines and keep the history.
+    ///   * `out`: Where to write results and diagnostics.
+    ///
+    pub fn new(commands: CommandRegistry, loader: Loader, source: &'a mut dyn LineSource,
+        out: &'a mut dyn Write) -> Self {
+        let colour = source.is_terminal();
+        Session {
+            universe: Universe::new(),
+            loader,
+            sources: Sources::new(),
+            commands: Rc::new(commands),
+            source,
+            out,
+            colour,
+            line: 0,
+            quit: false,
+            results: vec![],
+        }
+    }
+
+    /// Write a diagnostic, quoting the lines it refers to.
+    pub fn report(&mut self, diagnostic: &Diagnostic) {
+        report(self.out, self.colour, diagnostic, &self.sources);
+    }
+
+    /// Print the result of evaluating a term, and keep it as the next numbered result.
+    pub fn show(&mut self, term: &Arc<Term>) {
+        record(self.out, &mut self.results, &self.universe, term);
+    }
+
+    /// Get the values of the variables for the results: `$_N` for the result numbered N, and
+    /// `$_` for the last result.  The names are given without the dollar sign.
+    pub fn bindings(&self) -> Vec<(String, Arc<Term>)> {
+        let mut bindings: Vec<(String, Arc<Term>)> = self.results.iter().enumerate()
+            .map(|(index, term)| (format!("_{}", index + 1), term.clone()))
+            .collect();
+        if let Some(last) = self.results.last() {
+            bindings.push(("_".to_string(), last.clone()));
+        }
+        bindings
+    }
+
+    /// Load a file, printing and keeping the result of each term in it.  Errors are reported,
+    /// and `false` is returned if there was one.
+    ///
+    ///   * `name`: The path of the file.
+    ///   * `from`: Where the file is named, or `Locus::Internal`.
+    ///   * `width`: The number of characters naming the file at `from`.
+    ///
+    pub fn load(&mut self, name: &str, from: Locus, width: u32) -> bool {
+        let (out, results) = (&mut *self.out, &mut self.results);
+        let result = self.loader.load(&mut self.universe, &mut self.sources, name, from, width,
+            &mut |universe, term| record(out, results, universe, term));
+        match result {
+            Ok(()) => true,
+            Err(err) => {
+                self.report(&err.into_diagnostic());
+                false
+            }
+        }
+    }
+
+    /// Evaluate an entry, printing and keeping the result of each term in it.  Errors are
+    /// reported, and `false` is returned if there was one.
+    ///
+    ///   * `text`: The text of the entry.
+    ///   * `start`: Where the entry starts.
+    ///
+    pub fn evaluate(&mut self, text: &str, start: Locus) -> bool {
+        match self.perform(text, start.clone()) {
+            Ok(()) => true,
+            Err(err) => {
+                self.report(&diagnose(err, text, start));
+                false
+            }
+        }
+    }
+
+    /// Perform the statements of an entry, replacing the variables for the results in each
+    /// term first.  The first error stops the rest of the entry from being performed.
+    fn perform(&mut self, text: &str, start: Locus) -> Result<()> {
+        let mut parser = EliParser::new(text, start);
+        while let Some(statement) = parser.next_statement(self.universe.factory())? {
+            let statement = match statement {
+                Statement::Term(term) => {
+                    Statement::Term(bind(self.universe.factory(), &term, &self.bindings()))
+                }
+                statement => statement,
+            };
+            let (out, results) = (&mut *self.out, &mut self.results);
+            self.loader.perform(&mut self.universe, &mut self.sources, statement,
+                &mut |universe, term| record(out, results, universe, term))?;
+        }
+        Ok(())
+    }
+}
+
+/// Print a result and keep it as the next numbered result.
+///
+///   * `out`: Where to write the result.
+///   * `results`: The results so far.
+///   * `universe`: The universe that gives meaning to the result.
+///   * `term`: The result.
+///
+fn record(out: &mut dyn Write, results: &mut Vec<Arc<Term>>, universe: &Universe,
+    term: &Arc<Term>) {
+    show(out, universe, term);
+    results.push(term.clone());
+}
+
+/// Make the diagnostic for an error in an entry.  Errors without a diagnostic of their own
+/// point at the first line of the entry.
+///
+///   * `err`: The error.
+///   * `input`: The text of the entry.
+///   * `start`: Where the entry starts.
+///
+pub fn diagnose(err: Error, input: &str, start: Locus) -> Diagnostic {
+    match err.diagnostic() {
+        Some(_) => err.into_diagnostic(),
+        None => {
+            let width = input.lines().next().map_or(1, |line| line.chars().count());
+            err.into_diagnostic().with_primary(start, width as u32, "")
+        }
+    }
+}
+
+/// Print the result of evaluating a term.  The term is laid out in the width left after the
+/// prefix, with the universe's writer options, and continuation lines are indented under the
+/// prefix.  If the output has been closed, as when it is piped to a program that stops reading,
+/// the result is dropped.
+///
+///   * `out`: Where to write the result.
+///   * `universe`: The universe that gives meaning to the term.
+///   * `term`: The result.
+///
+pub fn show(out: &mut dyn Write, universe: &Universe, term: &Arc<Term>) {
+    let writer = EliWriter::new()
+        .with_config(universe.writer().config().clone())
+        .with_width(WIDTH - PREFIX.len());
+    let text = writer.display(universe.factory(), term).to_string();
+    let margin = format!("\n{}", " ".repeat(PREFIX.len()));
+    let _ = writeln!(out, "{}{}", PREFIX, text.replace('\n', &margin));
+}
+
+/// Write a diagnostic, quoting the lines it refers to.
+///
+///   * `out`: Where to write the diagnostic.
+///   * `colour`: Whether to use colour, which should only be done for a terminal.
+///   * `diagnostic`: The diagnostic.
+///   * `sources`: The text of the lines.
+///
+pub fn report(out: &mut dyn Write, colour: bool, diagnostic: &Diagnostic, sources: &Sources) {
+    let _ = if colour {
+        write!(out, "{}", diagnostic.render_ansi(sources))
+    } else {
+        write!(out, "{}", diagnostic.render(sources))
+    };
+}
@@ -0,0 +1,491 @@
+//! Write and read terms in a compact binary form.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+The binary form preserves sharing.  A term is written as a table of nodes, where each node
+refers to its children by their index in the table.  A subterm that is shared (the same Arc)
+is written once, no matter how many times it is referenced, and children always come before
+their parents.  The last node in the table is the term itself.
+
+    magic      4 bytes     "RELB"
+    version    1 byte      VERSION
+    flags      1 byte      bit 0 is set if loci are present
+    count      varint      number of nodes
+    nodes      count times
+
+Each node starts with a tag byte.  Integers are unsigned LEB128 varints and strings are a
+varint byte length followed by UTF-8.  When loci are present, every node that has a locus
+writes it right after the tag.  File names are written in full the first time they are seen
+and by index after that.
+
+The well-known root terms and the shared Boolean constants of the factory are written by name,
+so that reading them back gives the factory's own instances.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use diagnostics::Diagnostic;
+use error::{Error, Result};
+use terms::locus::Locus;
+
+/// The magic bytes that start every binary term.
+const MAGIC: &[u8; 4] = b"RELB";
+
+/// The version of the binary form written by this library.
+pub const VERSION: u8 = 1;
+
+/// The deepest term that is read.  Terms are made from a flat table without recursion, but
+/// everything done with them afterwards, including dropping them, recurses once per level.
+const MAX_DEPTH: usize = 10_000;
+
+/// Flag set when loci are present.
+const FLAG_LOCI: u8 = 1;
+
+// Node tags.
+const TAG_ROOT: u8 = 0;
+const TAG_NAMED: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_FALSE: u8 = 3;
+const TAG_SYMBOL: u8 = 4;
+const TAG_STRING: u8 = 5;
+const TAG_BOOLEAN: u8 = 6;
+const TAG_VARIABLE: u8 = 7;
+const TAG_STATIC_MAP: u8 = 8;
+const TAG_STATIC_PRODUCT: u8 = 9;
+const TAG_LAMBDA: u8 = 10;
+
+// Locus tags.
+const LOCUS_INTERNAL: u8 = 0;
+const LOCUS_CONSOLE: u8 = 1;
+const LOCUS_NEW_FILE: u8 = 2;
+const LOCUS_FILE: u8 = 3;
+
+/// Write terms in binary form.
+pub struct BinaryWriter {
+    /// Whether to write loci.
+    loci: bool,
+}
+
+impl Default for BinaryWriter {
+    fn default() -> Self {
+        BinaryWriter::new()
+    }
+}
+
+impl BinaryWriter {
+    /// Make a new binary writer that does not write loci.
+    pub fn new() -> Self {
+        BinaryWriter { loci: false }
+    }
+
+    /// Choose whether loci are written.  Without loci, terms are read back with an internal
+    /// locus.
+    pub fn with_loci(mut self, loci: bool) -> Self {
+        self.loci = loci;
+        self
+    }
+
+    /// Write a term.
+    pub fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        let mut table = Table {
+            fact,
+            loci: self.loci,
+            seen: HashMap::new(),
+            files: HashMap::new(),
+            count: 0,
+            body: vec![],
+        };
+        table.node(term);
+        dest.write_all(MAGIC)?;
+        dest.write_all(&[VERSION, if self.loci { FLAG_LOCI } else { 0 }])?;
+        let mut count = vec![];
+        put_varint(&mut count, table.count);
+        dest.write_all(&count)?;
+        dest.write_all(&table.body)
+    }
+}
+
+/// The node table being built by the writer.
+struct Table<'a> {
+    fact: &'a TermFactory,
+    loci: bool,
+    /// Map each term already written to its index.
+    seen: HashMap<*const Term, u64>,
+    /// Map each file name already written to its index.
+    files: HashMap<String, u64>,
+    /// The number of nodes written.
+    count: u64,
+    /// The encoded nodes.
+    body: Vec<u8>,
+}
+
+impl<'a> Table<'a> {
+    /// Write a node and its children, if they have not already been written, and return the
+    /// index of the node.
+    fn node(&mut self, term: &Arc<Term>) -> u64 {
+        let key = &**term as *const Term;
+        if let Some(&index) = self.seen.get(&key) {
+            return index;
+        }
+        match **term {
+            Term::Root => self.body.push(TAG_ROOT),
+            Term::SymbolLiteral { ref locus, ref typ, ref value } => {
+                let named = *locus == Locus::Internal && **typ == Term::Root &&
+                    self.fact.get_named_root_term(value).is_some_and(|t| Arc::ptr_eq(&t, term));
+                if named {
+                    self.body.push(TAG_NAMED);
+                    put_string(&mut self.body, value);
+                } else {
+                    let typ = self.node(typ);
+                    self.body.push(TAG_SYMBOL);
+                    self.locus(locus);
+                    put_varint(&mut self.body, typ);
+                    put_string(&mut self.body, value);
+                }
+            }
+            Term::StringLiteral { ref locus, ref typ, ref value } => {
+                let typ = self.node(typ);
+                self.body.push(TAG_STRING);
+                self.locus(locus);
+                put_varint(&mut self.body, typ);
+                put_string(&mut self.body, value);
+            }
+            Term::BooleanLiteral { ref locus, ref typ, value } => {
+                if Arc::ptr_eq(term, &self.fact.new_boolean(value)) {
+                    self.body.push(if value { TAG_TRUE } else { TAG_FALSE });
+                } else {
+                    let typ = self.node(typ);
+                    self.body.push(TAG_BOOLEAN);
+                    self.locus(locus);
+                    put_varint(&mut self.body, typ);
+                    self.body.push(value as u8);
+                }
+            }
+            Term::Variable { ref locus, ref typ, ref name, ref guard } => {
+                let typ = self.node(typ);
+                let guard = self.node(guard);
+                self.body.push(TAG_VARIABLE);
+                self.locus(locus);
+                put_varint(&mut self.body, typ);
+                put_string(&mut self.body, name);
+                put_varint(&mut self.body, guard);
+            }
+            Term::StaticMap { ref locus, ref domain, ref codomain } => {
+                let domain = self.node(domain);
+                let codomain = self.node(codomain);
+                self.body.push(TAG_STATIC_MAP);
+                self.locus(locus);
+                put_varint(&mut self.body, domain);
+                put_varint(&mut self.body, codomain);
+            }
+            Term::StaticProduct { ref locus, ref lhs, ref rhs } => {
+                let lhs = self.node(lhs);
+                let rhs = self.node(rhs);
+                self.body.push(TAG_STATIC_PRODUCT);
+                self.locus(locus);
+                put_varint(&mut self.body, lhs);
+                put_varint(&mut self.body, rhs);
+            }
+            Term::Lambda { ref locus, ref param, ref body, ref guard } => {
+                let param = self.node(param);
+                let body = self.node(body);
+                let guard = self.node(guard);
+                self.body.push(TAG_LAMBDA);
+                self.locus(locus);
+                put_varint(&mut self.body, param);
+                put_varint(&mut self.body, body);
+                put_varint(&mut self.body, guard);
+            }
+        }
+        let index = self.count;
+        self.count += 1;
+        self.seen.insert(key, index);
+        index
+    }
+
+    /// Write a locus, if loci are being written.
+    fn locus(&mut self, locus: &Locus) {
+        if !self.loci {
+            return;
+        }
+        match *locus {
+            Locus::Internal => self.body.push(LOCUS_INTERNAL),
+            Locus::Console(line, column) => {
+                self.body.push(LOCUS_CONSOLE);
+                put_varint(&mut self.body, line as u64);
+                put_varint(&mut self.body, column as u64);
+            }
+            Locus::File(ref name, line, column) => {
+                match self.files.get(name) {
+                    Some(&index) => {
+                        self.body.push(LOCUS_FILE);
+                        put_varint(&mut self.body, index);
+                    }
+                    None => {
+                        let index = self.files.len() as u64;
+                        self.files.insert(name.clone(), index);
+                        self.body.push(LOCUS_NEW_FILE);
+                        put_string(&mut self.body, name);
+                    }
+                }
+                put_varint(&mut self.body, line as u64);
+                put_varint(&mut self.body, column as u64);
+            }
+        }
+    }
+}
+
+/// Append an unsigned LEB128 varint.
+fn put_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Append a length-prefixed UTF-8 string.
+fn put_string(out: &mut Vec<u8>, value: &str) {
+    put_varint(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// Read terms in binary form.
+pub struct BinaryReader<'a> {
+    /// The factory used to make terms.
+    fact: &'a TermFactory,
+    /// The bytes being read.
+    bytes: Vec<u8>,
+    /// The offset of the next byte to read.
+    pos: usize,
+    /// Whether loci are present.
+    loci: bool,
+    /// The file names seen so far.
+    files: Vec<String>,
+    /// The depth of each node read so far.
+    depths: Vec<usize>,
+    /// The depth of the deepest child of the node being read.
+    deepest: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    /// Read a term written by a `BinaryWriter`.  The whole source is read.
+    pub fn read(src: &mut dyn io::Read, fact: &TermFactory) -> Result<Arc<Term>> {
+        let mut bytes = vec![];
+        src.read_to_end(&mut bytes)?;
+        let mut reader = BinaryReader { fact, bytes, pos: 0, loci: false, files: vec![],
+            depths: vec![], deepest: 0 };
+        reader.term()
+    }
+
+    /// Read the header and the node table.
+    fn term(&mut self) -> Result<Arc<Term>> {
+        if self.bytes.len() < 6 || &self.bytes[0..4] != MAGIC {
+            return Err(self.error("this is not a binary relision term"));
+        }
+        let version = self.bytes[4];
+        if version != VERSION {
+            return Err(self.error(&format!(
+                "binary version {} is not the supported version {}", version, VERSION)));
+        }
+        self.loci = self.bytes[5] & FLAG_LOCI != 0;
+        self.pos = 6;
+        let count = self.varint()?;
+        let mut nodes: Vec<Arc<Term>> = vec![];
+        for _ in 0..count {
+            self.deepest = 0;
+            let node = self.node(&nodes)?;
+            if self.deepest >= MAX_DEPTH {
+                return Err(self.error(&format!("the term is nested more than {} deep",
+                    MAX_DEPTH)));
+            }
+            self.depths.push(self.deepest + 1);
+            nodes.push(node);
+        }
+        if self.pos != self.bytes.len() {
+            return Err(self.error("unexpected data after the last node"));
+        }
+        match nodes.pop() {
+            Some(term) => Ok(term),
+            None => Err(self.error("there are no nodes")),
+        }
+    }
+
+    /// Read a single node.
+    fn node(&mut self, nodes: &[Arc<Term>]) -> Result<Arc<Term>> {
+        let fact = self.fact;
+        let tag = self.byte()?;
+        let term = match tag {
+            TAG_ROOT => fact.get_root(),
+            TAG_NAMED => {
+                let name = self.string()?;
+                match fact.get_named_root_term(&name) {
+                    Some(term) => term,
+                    None => fact.new_typed_symbol(Locus::Internal, name, fact.get_root()),
+                }
+            }
+            TAG_TRUE => fact.new_boolean(true),
+            TAG_FALSE => fact.new_boolean(false),
+            TAG_SYMBOL => {
+                let locus = self.locus()?;
+                let typ = self.child(nodes)?;
+                let value = self.string()?;
+                fact.new_typed_symbol(locus, value, typ)
+            }
+            TAG_STRING => {
+                let locus = self.locus()?;
+                let typ = self.child(nodes)?;
+                let value = self.string()?;
+                fact.new_typed_string(locus, value, typ)
+            }
+            TAG_BOOLEAN => {
+                let locus = self.locus()?;
+                let typ = self.child(nodes)?;
+                let value = self.byte()? != 0;
+                fact.new_typed_boolean(locus, value, typ)
+            }
+            TAG_VARIABLE => {
+                let locus = self.locus()?;
+                let typ = self.child(nodes)?;
+                let name = self.string()?;
+                let guard = self.child(nodes)?;
+                fact.new_variable(locus, &typ, name, &guard)
+            }
+            TAG_STATIC_MAP => {
+                let locus = self.locus()?;
+                let domain = self.child(nodes)?;
+                let codomain = self.child(nodes)?;
+                fact.new_static_map(locus, &domain, &codomain)
+            }
+            TAG_STATIC_PRODUCT => {
+                let locus = self.locus()?;
+                let lhs = self.child(nodes)?;
+                let rhs = self.child(nodes)?;
+                fact.new_static_product(locus, &lhs, &rhs)
+            }
+            TAG_LAMBDA => {
+                let locus = self.locus()?;
+                let param = self.child(nodes)?;
+                let body = self.child(nodes)?;
+                let guard = self.child(nodes)?;
+                fact.new_lambda(locus, &param, &body, &guard)
+            }
+            _ => return Err(self.error(&format!("unknown node tag {}", tag))),
+        };
+        Ok(term)
+    }
+
+    /// Read a reference to a node that has already been read.
+    fn child(&mut self, nodes: &[Arc<Term>]) -> Result<Arc<Term>> {
+        let index = self.varint()?;
+        match nodes.get(index as usize) {
+            Some(term) => {
+                self.deepest = self.deepest.max(self.depths[index as usize]);
+                Ok(term.clone())
+            }
+            None => Err(self.error(&format!("reference to node {} before it is defined", index))),
+        }
+    }
+
+    /// Read a locus, if loci are present.
+    fn locus(&mut self) -> Result<Locus> {
+        if !self.loci {
+            return Ok(Locus::Internal);
+        }
+        match self.byte()? {
+            LOCUS_INTERNAL => Ok(Locus::Internal),
+            LOCUS_CONSOLE => {
+                let line = self.varint()? as u32;
+                let column = self.varint()? as u32;
+                Ok(Locus::Console(line, column))
+            }
+            LOCUS_NEW_FILE => {
+                let name = self.string()?;
+                self.files.push(name.clone());
+                let line = self.varint()? as u32;
+                let column = self.varint()? as u32;
+                Ok(Locus::File(name, line, column))
+            }
+            LOCUS_FILE => {
+                let index = self.varint()? as usize;
+                let name = match self.files.get(index) {
+                    Some(name) => name.clone(),
+                    None => return Err(self.error(&format!("unknown file index {}", index))),
+                };
+                let line = self.varint()? as u32;
+                let column = self.varint()? as u32;
+                Ok(Locus::File(name, line, column))
+            }
+            tag => Err(self.error(&format!("unknown locus tag {}", tag))),
+        }
+    }
+
+    /// Read a byte.
+    fn byte(&mut self) -> Result<u8> {
+        match self.bytes.get(self.pos) {
+            Some(&byte) => {
+                self.pos += 1;
+                Ok(byte)
+            }
+            None => Err(self.error("unexpected end of the data")),
+        }
+    }
+
+    /// Read an unsigned LEB128 varint.
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return Err(self.error("integer is too large"));
+            }
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read a length-prefixed UTF-8 string.
+    fn string(&mut self) -> Result<String> {
+        let length = self.varint()? as usize;
+        if self.bytes.len() - self.pos < length {
+            return Err(self.error("unexpected end of the data"));
+        }
+        let bytes = self.bytes[self.pos..self.pos + length].to_vec();
+        self.pos += length;
+        String::from_utf8(bytes).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+
+    /// Make an error for the current position.
+    fn error(&self, message: &str) -> Error {
+        Error::Parse(Box::new(Diagnostic::error(message)
+            .with_note(&format!("at byte offset {}", self.pos))))
+    }
+}
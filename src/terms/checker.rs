@@ -0,0 +1,125 @@
+//! Check that the types given in terms are types.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+A type is a root term (ROOT itself or a named root, such as one made by a `root` declaration),
+a static map or static product of types, or a variable, which stands for a type.  A name that
+has not been declared as a root is read as a symbol, so giving it as a type is an error.  The
+error points at the name, with a secondary label on the term it was given to.
+*/
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use diagnostics::Diagnostic;
+use error::{Error, Result};
+
+/// Check that every type given to a literal or variable in a term, at any depth, is a type.
+pub fn check(fact: &TermFactory, term: &Arc<Term>) -> Result<()> {
+    walk(fact, term, &mut HashSet::new())
+}
+
+/// Check that a term is a type, as for the type of an operator.
+pub fn check_type(fact: &TermFactory, typ: &Arc<Term>) -> Result<()> {
+    match not_a_type(typ) {
+        Some(part) => Err(error(fact, part, None)),
+        None => Ok(()),
+    }
+}
+
+/// Check a term and its parts.  Shared parts are checked once.
+fn walk(fact: &TermFactory, term: &Arc<Term>, seen: &mut HashSet<*const Term>) -> Result<()> {
+    if !seen.insert(&**term as *const Term) {
+        return Ok(());
+    }
+    match **term {
+        Term::SymbolLiteral { ref typ, .. } | Term::StringLiteral { ref typ, .. }
+            | Term::BooleanLiteral { ref typ, .. } => given(fact, term, typ),
+        Term::Variable { ref typ, ref guard, .. } => {
+            given(fact, term, typ)?;
+            walk(fact, guard, seen)
+        }
+        Term::StaticMap { ref domain, ref codomain, .. } => {
+            walk(fact, domain, seen)?;
+            walk(fact, codomain, seen)
+        }
+        Term::StaticProduct { ref lhs, ref rhs, .. } => {
+            walk(fact, lhs, seen)?;
+            walk(fact, rhs, seen)
+        }
+        Term::Lambda { ref param, ref body, ref guard, .. } => {
+            walk(fact, param, seen)?;
+            walk(fact, body, seen)?;
+            walk(fact, guard, seen)
+        }
+        Term::Root => Ok(()),
+    }
+}
+
+/// Check the type given to a term.
+fn given(fact: &TermFactory, term: &Arc<Term>, typ: &Arc<Term>) -> Result<()> {
+    match not_a_type(typ) {
+        Some(part) => Err(error(fact, part, Some(term))),
+        None => Ok(()),
+    }
+}
+
+/// Find the part of a would-be type that is not a type, if there is one.
+fn not_a_type(typ: &Arc<Term>) -> Option<&Arc<Term>> {
+    match **typ {
+        Term::Root | Term::Variable { .. } => None,
+        Term::SymbolLiteral { typ: ref kind, .. } if **kind == Term::Root => None,
+        Term::StaticMap { ref domain, ref codomain, .. } => {
+            not_a_type(domain).or_else(|| not_a_type(codomain))
+        }
+        Term::StaticProduct { ref lhs, ref rhs, .. } => {
+            not_a_type(lhs).or_else(|| not_a_type(rhs))
+        }
+        _ => Some(typ),
+    }
+}
+
+/// Make the error for a part of a type that is not a type.
+///
+///   * `fact`: The factory that made the terms.
+///   * `part`: The part that is not a type.
+///   * `term`: The term the type was given to, if any.
+///
+fn error(fact: &TermFactory, part: &Arc<Term>, term: Option<&Arc<Term>>) -> Error {
+    let mut diagnostic = match **part {
+        Term::SymbolLiteral { ref value, .. } => {
+            Diagnostic::error(&format!("{} is not a type", value))
+                .with_primary(fact.get_locus(part), value.chars().count() as u32,
+                    "not a declared root")
+                .with_note(&format!("declare it with `root {};`", value))
+        }
+        _ => Diagnostic::error("this term is not a type")
+            .with_primary(fact.get_locus(part), 1, "expected a type"),
+    };
+    if let Some(term) = term {
+        let width = match **term {
+            Term::SymbolLiteral { ref value, .. } => value.chars().count() as u32,
+            Term::Variable { ref name, .. } => name.chars().count() as u32 + 1,
+            _ => 1,
+        };
+        diagnostic = diagnostic.with_secondary(fact.get_locus(term), width, "given the type here");
+    }
+    Error::Type(Box::new(diagnostic))
+}
@@ -0,0 +1,211 @@
+//! Write terms as Graphviz graphs.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+The term is written as a directed graph in the DOT language.  Every distinct subterm (every
+distinct Arc) becomes one node, so shared subterms show up as nodes with more than one incoming
+edge.  Edges are labeled and coloured by the role the child plays in its parent.
+
+Type edges follow the writer configuration, so by default only types that are not the default
+for their kind of term are shown.  Most types are root terms such as STRING or ^ROOT, and edges
+to these can also be left out to reduce clutter.  If loci are written, they are added to the
+node labels.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::util::{TermWriter, WriterConfig};
+use terms::locus::Locus;
+use util::escape_with;
+
+/// Write a term as a Graphviz graph.
+pub struct DotWriter {
+    /// The options shared by all writers.
+    config: WriterConfig,
+    /// Whether to show type edges that lead to root terms.
+    root_types: bool,
+}
+
+impl DotWriter {
+    /// Choose whether type edges that lead to `^ROOT` or to a named root term are shown.
+    /// They are shown by default.
+    pub fn with_root_types(mut self, show: bool) -> Self {
+        self.root_types = show;
+        self
+    }
+}
+
+impl TermWriter for DotWriter {
+    fn new() -> Self {
+        DotWriter { config: WriterConfig::new(), root_types: true }
+    }
+
+    fn config(&self) -> &WriterConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: WriterConfig) {
+        self.config = config;
+    }
+
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        writeln!(dest, "digraph term {{")?;
+        writeln!(dest, "    node [shape=box, fontname=\"monospace\"];")?;
+        let mut graph = Graph { writer: self, fact, dest, seen: HashMap::new() };
+        graph.node(term)?;
+        write!(dest, "}}")
+    }
+}
+
+/// The roles a child can play in its parent.
+#[derive(Clone, Copy)]
+enum Role {
+    Type,
+    Guard,
+    Domain,
+    Codomain,
+    Lhs,
+    Rhs,
+    Param,
+    Body,
+}
+
+impl Role {
+    /// Get the label and colour of edges for this role.
+    fn style(&self) -> (&'static str, &'static str) {
+        match *self {
+            Role::Type => ("type", "gray50"),
+            Role::Guard => ("guard", "red"),
+            Role::Domain => ("domain", "blue"),
+            Role::Codomain => ("codomain", "darkgreen"),
+            Role::Lhs => ("lhs", "darkorange"),
+            Role::Rhs => ("rhs", "brown"),
+            Role::Param => ("param", "purple"),
+            Role::Body => ("body", "black"),
+        }
+    }
+}
+
+/// The state of a single write.
+struct Graph<'a> {
+    writer: &'a DotWriter,
+    fact: &'a TermFactory,
+    dest: &'a mut dyn io::Write,
+    /// Map each node already written to its identifier.
+    seen: HashMap<*const Term, usize>,
+}
+
+impl<'a> Graph<'a> {
+    /// Write a node, its children, and the edges to them, if the node has not already been
+    /// written.  Return the identifier of the node.
+    fn node(&mut self, term: &Arc<Term>) -> io::Result<usize> {
+        let key = &**term as *const Term;
+        if let Some(&id) = self.seen.get(&key) {
+            return Ok(id);
+        }
+        let id = self.seen.len();
+        self.seen.insert(key, id);
+        let ascii = self.writer.config.ascii;
+        let (mut label, children) = match **term {
+            Term::Root => ("^ROOT".to_string(), vec![]),
+            Term::SymbolLiteral { ref typ, ref value, .. } => {
+                let (escaped, modified) = escape_with(value, '`', ascii);
+                let name = if modified { format!("`{}`", escaped) } else { escaped };
+                if **typ == Term::Root && self.fact.is_named_root_term(value) {
+                    (name, vec![(Role::Type, typ)])
+                } else {
+                    (format!("symbol\n{}", name), vec![(Role::Type, typ)])
+                }
+            }
+            Term::StringLiteral { ref typ, ref value, .. } => {
+                let (escaped, _) = escape_with(value, '"', ascii);
+                (format!("string\n\"{}\"", escaped), vec![(Role::Type, typ)])
+            }
+            Term::BooleanLiteral { ref typ, value, .. } => {
+                (format!("boolean\n{}", value), vec![(Role::Type, typ)])
+            }
+            Term::Variable { ref typ, ref name, ref guard, .. } => {
+                let (escaped, modified) = escape_with(name, '`', ascii);
+                let name = if modified { format!("`{}`", escaped) } else { escaped };
+                (format!("variable\n${}", name), vec![(Role::Type, typ), (Role::Guard, guard)])
+            }
+            Term::StaticMap { ref domain, ref codomain, .. } => {
+                ("static map".to_string(),
+                    vec![(Role::Domain, domain), (Role::Codomain, codomain)])
+            }
+            Term::StaticProduct { ref lhs, ref rhs, .. } => {
+                ("static product".to_string(), vec![(Role::Lhs, lhs), (Role::Rhs, rhs)])
+            }
+            Term::Lambda { ref param, ref body, ref guard, .. } => {
+                ("lambda".to_string(),
+                    vec![(Role::Param, param), (Role::Body, body), (Role::Guard, guard)])
+            }
+        };
+        if self.writer.config.loci {
+            let locus = self.fact.get_locus(term);
+            if locus != Locus::Internal {
+                label = format!("{}\n{}", label, locus);
+            }
+        }
+        writeln!(self.dest, "    n{} [label={}];", id, quote(&label))?;
+        for (role, child) in children {
+            if let Role::Type = role {
+                if !self.writer.config.shows_type(self.fact, term) ||
+                    (!self.writer.root_types && self.is_root_type(child)) {
+                    continue;
+                }
+            }
+            let child_id = self.node(child)?;
+            let (label, colour) = role.style();
+            writeln!(self.dest, "    n{} -> n{} [label=\"{}\", color=\"{}\", fontcolor=\"{}\"];",
+                id, child_id, label, colour, colour)?;
+        }
+        Ok(id)
+    }
+
+    /// Determine if a term is `^ROOT` or a named root term.
+    fn is_root_type(&self, term: &Arc<Term>) -> bool {
+        match **term {
+            Term::Root => true,
+            Term::SymbolLiteral { ref typ, ref value, .. } => {
+                **typ == Term::Root && self.fact.is_named_root_term(value)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Make a DOT string literal.  Line breaks in the text become centred line breaks.
+fn quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,762 @@
+//! Read relision terms and declarations written in the relision language.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+The parser reads the same language the ELI writer produces.  Input is a sequence of statements,
+each ended by a semicolon (the final semicolon may be omitted).  A statement is either a term or
+one of the following declarations.
+
+    root NAME;                          Declare a new named root term.
+    operator NAME: TYPE ["description"];  Declare an operator.
+    rule PATTERN ->{GUARD} REWRITE [in RULESET, ...];  Declare a rule.
+    enable RULESET;                     Enable a ruleset.
+    disable RULESET;                    Disable a ruleset.
+    set NAME = VALUE;                   Change a setting.
+    load "PATH";                        Load the statements in another file.
+
+The term grammar is the following, from loosest to tightest binding.  Line comments start with
+// and block comments are enclosed in /* and */.
+
+    term    := "let" binding { "," binding } "in" term  Bindings for shared subterms.
+             | map [ "->" [ "{" term "}" ] term ]       Lambda (right associative).
+    binding := "#" NAME "=" term
+    map     := product [ "=>" map ]                     Static map (right associative).
+    product := typed { "*" typed }                      Static product (left associative).
+    typed   := primary [ ":" typed ]                    Type annotation.
+    primary := "^ROOT" | NAME | "`" ... "`" | "\"" ... "\"" | "true" | "false"
+             | "$" NAME [ "{" term "}" ] | "#" NAME | "(" term ")"
+
+A reference #NAME stands for the term bound to that name by an enclosing let, or by an earlier
+binding of the same let.  Every reference is the same term, so sharing is preserved.
+
+Because a root term may be declared part way through the input, the parser does not hold on to
+a term factory.  Instead each statement is parsed with the factory given at that point.
+*/
+
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::operators::Operator;
+use super::rules::Rule;
+use diagnostics::Diagnostic;
+use error::{Error, Result};
+use terms::locus::Locus;
+use util::unescape;
+
+/// A statement read from the input.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    /// A term to be evaluated.
+    Term(Arc<Term>),
+    /// Declare a new named root term.
+    Root(Locus, String),
+    /// Declare an operator.
+    Operator(Operator),
+    /// Declare a rule.
+    Rule(Rule),
+    /// Enable a ruleset.
+    Enable(Locus, String),
+    /// Disable a ruleset.
+    Disable(Locus, String),
+    /// Change a setting.
+    Set(Locus, String, String),
+    /// Load the statements in another file.  The locus and width are those of the path, so
+    /// that diagnostics about the file can mark it.
+    Load(Locus, String, u32),
+}
+
+/// The kinds of token.
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    /// A bare name.  Keywords are names, too.
+    Name(String),
+    /// A name enclosed in backticks.
+    Quoted(String),
+    /// A string literal.
+    Str(String),
+    /// A variable name, without the dollar sign.
+    Var(String),
+    /// A reference to a binding, without the hash sign.
+    Ref(String),
+    /// A sequence of decimal digits.
+    Number(String),
+    /// The root term.
+    Root,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Colon,
+    Semi,
+    Comma,
+    Equals,
+    Arrow,
+    MapArrow,
+    Star,
+    Eof,
+}
+
+impl Kind {
+    /// Describe the token for use in error messages.
+    fn describe(&self) -> String {
+        match *self {
+            Kind::Name(ref name) => format!("`{}`", name),
+            Kind::Quoted(_) => "a quoted symbol".to_string(),
+            Kind::Str(_) => "a string".to_string(),
+            Kind::Var(_) => "a variable".to_string(),
+            Kind::Ref(ref name) => format!("`#{}`", name),
+            Kind::Number(ref digits) => format!("the number {}", digits),
+            Kind::Root => "`^ROOT`".to_string(),
+            Kind::LParen => "`(`".to_string(),
+            Kind::RParen => "`)`".to_string(),
+            Kind::LBrace => "`{`".to_string(),
+            Kind::RBrace => "`}`".to_string(),
+            Kind::Colon => "`:`".to_string(),
+            Kind::Semi => "`;`".to_string(),
+            Kind::Comma => "`,`".to_string(),
+            Kind::Equals => "`=`".to_string(),
+            Kind::Arrow => "`->`".to_string(),
+            Kind::MapArrow => "`=>`".to_string(),
+            Kind::Star => "`*`".to_string(),
+            Kind::Eof => "the end of the input".to_string(),
+        }
+    }
+}
+
+/// A token, together with where it was found.
+#[derive(Clone, Debug)]
+struct Token {
+    kind: Kind,
+    line: u32,
+    column: u32,
+    width: u32,
+}
+
+/// Read statements and terms from text.
+pub struct EliParser {
+    /// The characters of the input.
+    chars: Vec<char>,
+    /// The position of the next character to read.
+    pos: usize,
+    /// The line of the next character to read.
+    line: u32,
+    /// The column of the next character to read.
+    column: u32,
+    /// The locus the input starts at.  Every locus made by the parser has the same form.
+    start: Locus,
+    /// The next token, if it has been read.
+    peeked: Option<Token>,
+    /// The terms bound by the enclosing lets, innermost last.
+    bindings: Vec<(String, Arc<Term>)>,
+}
+
+impl EliParser {
+    /// Make a parser for the given text.
+    ///   * `text`: The text to parse.
+    ///   * `start`: Where the text starts.  Use a console locus for lines typed at the REPL
+    ///     and a file locus for files.  An internal locus is treated as line 1 of the console.
+    pub fn new(text: &str, start: Locus) -> Self {
+        let (line, column) = match start {
+            Locus::Internal => (1, 1),
+            Locus::Console(line, column) => (line, column),
+            Locus::File(_, line, column) => (line, column),
+        };
+        let start = match start {
+            Locus::Internal => Locus::Console(1, 1),
+            other => other,
+        };
+        EliParser {
+            chars: text.chars().collect(),
+            pos: 0,
+            line,
+            column,
+            start,
+            peeked: None,
+            bindings: vec![],
+        }
+    }
+
+    /// Parse all the statements in the input.  Root declarations are not performed, so the
+    /// input should not refer to roots that it declares.  Use `next_statement` when that
+    /// matters.
+    pub fn parse_all(&mut self, fact: &TermFactory) -> Result<Vec<Statement>> {
+        let mut statements = vec![];
+        while let Some(statement) = self.next_statement(fact)? {
+            statements.push(statement);
+        }
+        Ok(statements)
+    }
+
+    /// Parse the next statement, or return `None` at the end of the input.
+    pub fn next_statement(&mut self, fact: &TermFactory) -> Result<Option<Statement>> {
+        // Skip empty statements.
+        while self.peek()?.kind == Kind::Semi {
+            self.next()?;
+        }
+        let token = self.peek()?;
+        let locus = self.locus(&token);
+        let statement = match token.kind {
+            Kind::Eof => return Ok(None),
+            Kind::Name(ref word) if word == "root" => {
+                self.next()?;
+                let name = self.name("a name for the root")?;
+                Statement::Root(locus, name)
+            }
+            Kind::Name(ref word) if word == "operator" => {
+                self.next()?;
+                let name = self.name("a name for the operator")?;
+                self.expect(Kind::Colon)?;
+                let typ = self.term(fact)?;
+                let description = match self.peek()?.kind {
+                    Kind::Str(ref text) => {
+                        let text = text.clone();
+                        self.next()?;
+                        text
+                    }
+                    _ => String::new(),
+                };
+                Statement::Operator(Operator { locus, name, typ, description })
+            }
+            Kind::Name(ref word) if word == "rule" => {
+                self.next()?;
+                let start = self.peek()?;
+                let term = self.term(fact)?;
+                let (pattern, rewrite, guard) = match *term {
+                    Term::Lambda { ref param, ref body, ref guard, .. } => {
+                        (param.clone(), body.clone(), guard.clone())
+                    }
+                    _ => {
+                        return Err(self.error(&start,
+                            "a rule must be written as PATTERN -> REWRITE", "not a rule"));
+                    }
+                };
+                let mut rulesets = vec![];
+                if self.peek()?.kind == Kind::Name("in".to_string()) {
+                    self.next()?;
+                    rulesets.push(self.name("the name of a ruleset")?);
+                    while self.peek()?.kind == Kind::Comma {
+                        self.next()?;
+                        rulesets.push(self.name("the name of a ruleset")?);
+                    }
+                }
+                Statement::Rule(Rule { locus, pattern, rewrite, guard, rulesets })
+            }
+            Kind::Name(ref word) if word == "enable" => {
+                self.next()?;
+                Statement::Enable(locus, self.name("the name of a ruleset")?)
+            }
+            Kind::Name(ref word) if word == "disable" => {
+                self.next()?;
+                Statement::Disable(locus, self.name("the name of a ruleset")?)
+            }
+            Kind::Name(ref word) if word == "set" => {
+                self.next()?;
+                let name = self.name("the name of a setting")?;
+                self.expect(Kind::Equals)?;
+                let token = self.next()?;
+                let value = match token.kind {
+                    Kind::Name(text) | Kind::Quoted(text) | Kind::Str(text) |
+                        Kind::Number(text) => text,
+                    _ => {
+                        let message = format!("expected a value for the setting but found {}",
+                            token.kind.describe());
+                        return Err(self.error(&token, &message, "expected a value"));
+                    }
+                };
+                Statement::Set(locus, name, value)
+            }
+            Kind::Name(ref word) if word == "load" => {
+                self.next()?;
+                let token = self.next()?;
+                let (at, width) = (self.locus(&token), token.width);
+                let path = match token.kind {
+                    Kind::Name(text) | Kind::Quoted(text) | Kind::Str(text) => text,
+                    _ => {
+                        let message = format!("expected the path of a file but found {}",
+                            token.kind.describe());
+                        return Err(self.error(&token, &message, "expected a path"));
+                    }
+                };
+                Statement::Load(at, path, width)
+            }
+            _ => Statement::Term(self.term(fact)?),
+        };
+
+        // Every statement ends with a semicolon or the end of the input.
+        let token = self.peek()?;
+        match token.kind {
+            Kind::Semi => {
+                self.next()?;
+            }
+            Kind::Eof => {}
+            _ => {
+                let message = format!("expected `;` but found {}", token.kind.describe());
+                return Err(self.error(&token, &message, "expected `;` before this"));
+            }
+        }
+        Ok(Some(statement))
+    }
+
+    /// Parse a single term.  The term may be followed by a semicolon, but nothing else.
+    pub fn parse_term(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        let term = self.term(fact)?;
+        if self.peek()?.kind == Kind::Semi {
+            self.next()?;
+        }
+        let token = self.peek()?;
+        if token.kind != Kind::Eof {
+            let message = format!("expected the end of the term but found {}",
+                token.kind.describe());
+            return Err(self.error(&token, &message, "unexpected"));
+        }
+        Ok(term)
+    }
+
+    /// Parse a term, which may be a lambda or have bindings.
+    fn term(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        let start = self.peek()?;
+        if start.kind == Kind::Name("let".to_string()) {
+            return self.bindings(fact);
+        }
+        let param = self.map(fact)?;
+        if self.peek()?.kind != Kind::Arrow {
+            return Ok(param);
+        }
+        self.next()?;
+        let guard = if self.peek()?.kind == Kind::LBrace {
+            self.next()?;
+            let guard = self.term(fact)?;
+            self.expect(Kind::RBrace)?;
+            guard
+        } else {
+            fact.new_boolean(true)
+        };
+        let body = self.term(fact)?;
+        Ok(fact.new_lambda(self.locus(&start), &param, &body, &guard))
+    }
+
+    /// Parse bindings and the term they are used in.  The `let` has not been read.
+    fn bindings(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        self.next()?;
+        let depth = self.bindings.len();
+        let result = self.bindings_body(fact);
+        self.bindings.truncate(depth);
+        result
+    }
+
+    /// Parse the rest of bindings, after the `let`.
+    fn bindings_body(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        loop {
+            let token = self.next()?;
+            let name = match token.kind {
+                Kind::Ref(name) => name,
+                ref other => {
+                    let message = format!("expected a name such as #1 but found {}",
+                        other.describe());
+                    return Err(self.error(&token, &message, "expected a name"));
+                }
+            };
+            self.expect(Kind::Equals)?;
+            let term = self.term(fact)?;
+            self.bindings.push((name, term));
+            if self.peek()?.kind != Kind::Comma {
+                break;
+            }
+            self.next()?;
+        }
+        let token = self.next()?;
+        if token.kind != Kind::Name("in".to_string()) {
+            let message = format!("expected `in` but found {}", token.kind.describe());
+            return Err(self.error(&token, &message, "expected `in`"));
+        }
+        self.term(fact)
+    }
+
+    /// Parse a static map or anything that binds more tightly.
+    fn map(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        let start = self.peek()?;
+        let domain = self.product(fact)?;
+        if self.peek()?.kind != Kind::MapArrow {
+            return Ok(domain);
+        }
+        self.next()?;
+        let codomain = self.map(fact)?;
+        Ok(fact.new_static_map(self.locus(&start), &domain, &codomain))
+    }
+
+    /// Parse a static product or anything that binds more tightly.
+    fn product(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        let start = self.peek()?;
+        let mut lhs = self.typed(fact)?;
+        while self.peek()?.kind == Kind::Star {
+            self.next()?;
+            let rhs = self.typed(fact)?;
+            lhs = fact.new_static_product(self.locus(&start), &lhs, &rhs);
+        }
+        Ok(lhs)
+    }
+
+    /// Parse a primary term with an optional type annotation.
+    fn typed(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        let start = self.peek()?;
+        let term = self.primary(fact)?;
+        if self.peek()?.kind != Kind::Colon {
+            return Ok(term);
+        }
+        self.next()?;
+        let typ = self.typed(fact)?;
+        let locus = fact.get_locus(&term);
+        match *term {
+            Term::SymbolLiteral { ref value, .. } => {
+                if *typ == Term::Root && fact.is_named_root_term(value) {
+                    Ok(fact.get_named_root_term(value).unwrap_or_else(|| term.clone()))
+                } else {
+                    Ok(fact.new_typed_symbol(locus, value.clone(), typ))
+                }
+            }
+            Term::StringLiteral { ref value, .. } => {
+                Ok(fact.new_typed_string(locus, value.clone(), typ))
+            }
+            Term::BooleanLiteral { value, .. } => Ok(fact.new_typed_boolean(locus, value, typ)),
+            Term::Variable { ref name, ref guard, .. } => {
+                Ok(fact.new_variable(locus, &typ, name.clone(), guard))
+            }
+            Term::Root if *typ == Term::Root => Ok(term.clone()),
+            _ => Err(self.error(&start, "the type of this term cannot be given",
+                "the type of this term is fixed")),
+        }
+    }
+
+    /// Parse a primary term.
+    fn primary(&mut self, fact: &TermFactory) -> Result<Arc<Term>> {
+        let token = self.next()?;
+        let locus = self.locus(&token);
+        match token.kind {
+            Kind::Root => Ok(fact.get_root()),
+            Kind::Name(ref name) if name == "true" || name == "false" => {
+                Ok(fact.new_typed_boolean(locus, name == "true", fact.get_boolean()))
+            }
+            Kind::Name(name) | Kind::Quoted(name) => {
+                match fact.get_named_root_term(&name) {
+                    Some(term) => Ok(term),
+                    None => Ok(fact.new_symbol(locus, name)),
+                }
+            }
+            Kind::Str(value) => Ok(fact.new_string(locus, value)),
+            Kind::Var(name) => {
+                let guard = if self.peek()?.kind == Kind::LBrace {
+                    self.next()?;
+                    let guard = self.term(fact)?;
+                    self.expect(Kind::RBrace)?;
+                    guard
+                } else {
+                    fact.new_boolean(true)
+                };
+                Ok(fact.new_variable(locus, &fact.get_any(), name, &guard))
+            }
+            Kind::Ref(ref name) => {
+                match self.bindings.iter().rev().find(|binding| binding.0 == *name) {
+                    Some(binding) => Ok(binding.1.clone()),
+                    None => {
+                        let message = format!("nothing is bound to #{}", name);
+                        Err(self.error(&token, &message, "not bound"))
+                    }
+                }
+            }
+            Kind::LParen => {
+                let term = self.term(fact)?;
+                self.expect(Kind::RParen)?;
+                Ok(term)
+            }
+            Kind::Number(_) => {
+                Err(self.error(&token, "numeric literals are not supported",
+                    "not a term"))
+            }
+            _ => {
+                let message = format!("expected a term but found {}", token.kind.describe());
+                Err(self.error(&token, &message, "expected a term"))
+            }
+        }
+    }
+
+    /// Read a name, which may be bare or quoted.
+    ///   * `what`: What the name is for, used in the error message.
+    fn name(&mut self, what: &str) -> Result<String> {
+        let token = self.next()?;
+        match token.kind {
+            Kind::Name(name) | Kind::Quoted(name) => Ok(name),
+            _ => {
+                let message = format!("expected {} but found {}", what, token.kind.describe());
+                Err(self.error(&token, &message, "expected a name"))
+            }
+        }
+    }
+
+    /// Read a token of the given kind, or fail.
+    fn expect(&mut self, kind: Kind) -> Result<Token> {
+        let token = self.next()?;
+        if token.kind == kind {
+            Ok(token)
+        } else {
+            let message = format!("expected {} but found {}", kind.describe(),
+                token.kind.describe());
+            let label = format!("expected {}", kind.describe());
+            Err(self.error(&token, &message, &label))
+        }
+    }
+
+    /// Make a parse error pointing at a token.
+    fn error(&self, token: &Token, message: &str, label: &str) -> Error {
+        Error::Parse(Box::new(Diagnostic::error(message)
+            .with_primary(self.locus(token), token.width, label)))
+    }
+
+    /// Make a parse error pointing at the current position.
+    fn error_here(&self, width: u32, message: &str, label: &str) -> Error {
+        Error::Parse(Box::new(Diagnostic::error(message)
+            .with_primary(self.locus_at(self.line, self.column), width, label)))
+    }
+
+    /// Get the locus of a token.
+    fn locus(&self, token: &Token) -> Locus {
+        self.locus_at(token.line, token.column)
+    }
+
+    /// Make a locus of the same form as the start locus.
+    fn locus_at(&self, line: u32, column: u32) -> Locus {
+        match self.start {
+            Locus::File(ref name, _, _) => Locus::File(name.clone(), line, column),
+            _ => Locus::Console(line, column),
+        }
+    }
+
+    /// Look at the next token without consuming it.
+    fn peek(&mut self) -> Result<Token> {
+        if self.peeked.is_none() {
+            let token = self.lex()?;
+            self.peeked = Some(token);
+        }
+        Ok(self.peeked.clone().unwrap())
+    }
+
+    /// Consume and return the next token.
+    fn next(&mut self) -> Result<Token> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lex(),
+        }
+    }
+
+    /// Look at the character at an offset from the current position.
+    fn look(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).cloned()
+    }
+
+    /// Consume a character, keeping track of the line and column.
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.look(0);
+        if let Some(ch) = ch {
+            self.pos += 1;
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        ch
+    }
+
+    /// Skip white space and comments.
+    fn skip(&mut self) -> Result<()> {
+        loop {
+            match (self.look(0), self.look(1)) {
+                (Some(ch), _) if ch.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => {
+                    while let Some(ch) = self.look(0) {
+                        if ch == '\n' {
+                            break;
+                        }
+                        self.bump();
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    let (line, column) = (self.line, self.column);
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match (self.look(0), self.look(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.bump();
+                                self.bump();
+                                break;
+                            }
+                            (Some(_), _) => {
+                                self.bump();
+                            }
+                            (None, _) => {
+                                return Err(Error::Parse(Box::new(
+                                    Diagnostic::error("unterminated block comment")
+                                        .with_primary(self.locus_at(line, column), 2,
+                                            "the comment starts here"))));
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Read the next token from the input.
+    fn lex(&mut self) -> Result<Token> {
+        self.skip()?;
+        let (line, column) = (self.line, self.column);
+        let start = self.pos;
+        let ch = match self.bump() {
+            None => return Ok(Token { kind: Kind::Eof, line, column, width: 1 }),
+            Some(ch) => ch,
+        };
+        let kind = match ch {
+            '(' => Kind::LParen,
+            ')' => Kind::RParen,
+            '{' => Kind::LBrace,
+            '}' => Kind::RBrace,
+            ':' => Kind::Colon,
+            ';' => Kind::Semi,
+            ',' => Kind::Comma,
+            '*' => Kind::Star,
+            '-' if self.look(0) == Some('>') => {
+                self.bump();
+                Kind::Arrow
+            }
+            '=' if self.look(0) == Some('>') => {
+                self.bump();
+                Kind::MapArrow
+            }
+            '=' => Kind::Equals,
+            '^' => {
+                let name = self.bare_name();
+                if name != "ROOT" {
+                    return Err(self.error_here(1, "expected `^ROOT`", "unknown root"));
+                }
+                Kind::Root
+            }
+            '"' => Kind::Str(self.delimited('"', line, column)?),
+            '`' => Kind::Quoted(self.delimited('`', line, column)?),
+            '$' => {
+                if self.look(0) == Some('`') {
+                    self.bump();
+                    Kind::Var(self.delimited('`', line, column)?)
+                } else {
+                    let name = self.bare_name();
+                    if name.is_empty() {
+                        return Err(self.error_here(1, "expected a variable name after `$`",
+                            "expected a name"));
+                    }
+                    Kind::Var(name)
+                }
+            }
+            '#' => {
+                let name = self.bare_name();
+                if name.is_empty() {
+                    return Err(self.error_here(1, "expected a name after `#`",
+                        "expected a name"));
+                }
+                Kind::Ref(name)
+            }
+            ch if ch.is_ascii_digit() => {
+                let mut digits = ch.to_string();
+                while let Some(ch) = self.look(0) {
+                    if !ch.is_ascii_digit() {
+                        break;
+                    }
+                    digits.push(ch);
+                    self.bump();
+                }
+                Kind::Number(digits)
+            }
+            ch if ch.is_alphabetic() || ch == '_' => {
+                let mut name = ch.to_string();
+                name.push_str(&self.bare_name());
+                Kind::Name(name)
+            }
+            ch => {
+                let message = format!("unexpected character {:?}", ch);
+                return Err(Error::Parse(Box::new(Diagnostic::error(&message)
+                    .with_primary(self.locus_at(line, column), 1, "not expected here"))));
+            }
+        };
+        Ok(Token { kind, line, column, width: (self.pos - start) as u32 })
+    }
+
+    /// Read the rest of a bare name.
+    fn bare_name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(ch) = self.look(0) {
+            if !(ch.is_alphanumeric() || ch == '_') {
+                break;
+            }
+            name.push(ch);
+            self.bump();
+        }
+        name
+    }
+
+    /// Read the rest of a delimited string or quoted name, interpreting escapes.  The
+    /// opening delimiter has already been read.
+    fn delimited(&mut self, border: char, line: u32, column: u32) -> Result<String> {
+        // Find the closing delimiter, then interpret the escapes in the text before it.
+        let (start_line, start_column) = (self.line, self.column);
+        let mut raw = String::new();
+        loop {
+            match self.bump() {
+                Some(ch) if ch == border => break,
+                Some('\\') => {
+                    raw.push('\\');
+                    if let Some(ch) = self.bump() {
+                        raw.push(ch);
+                    }
+                }
+                Some(ch) => raw.push(ch),
+                None => {
+                    let message = format!("missing closing {}", border);
+                    return Err(Error::Parse(Box::new(Diagnostic::error(&message)
+                        .with_primary(self.locus_at(line, column), 1, "opened here"))));
+                }
+            }
+        }
+        unescape(&raw, border).map_err(|err| {
+            // Find the line and column of the bad escape within the text.
+            let (mut eline, mut ecolumn) = (start_line, start_column);
+            for ch in raw.chars().take(err.offset) {
+                if ch == '\n' {
+                    eline += 1;
+                    ecolumn = 1;
+                } else {
+                    ecolumn += 1;
+                }
+            }
+            Error::Parse(Box::new(Diagnostic::error("invalid escape")
+                .with_primary(self.locus_at(eline, ecolumn), err.width as u32, &err.message)))
+        })
+    }
+}
@@ -17,151 +17,383 @@
 //! that is part of this distribution.  This file may not be copied,
 //! modified, or distributed except according to those terms.
 
+/*
+The operators bind as follows, from loosest to tightest.  A term written where only a tighter
+term is allowed is enclosed in parentheses.
+
+    p -> b      A lambda.  The parameter binds more tightly than a lambda, and the body extends
+                as far as possible.
+    d => c      A static map, which is right associative: a => b => c is a => (b => c).
+    l * r       A static product, which is left associative: a * b * c is (a * b) * c.
+    t: T        A type annotation on a literal or variable, which is right associative.
+
+Guards are enclosed in braces, so they never need parentheses.
+
+If the writer is configured to show sharing, every map, product, lambda, and guarded variable
+that would be written more than once is written once as a binding, and referred to by name
+everywhere it occurs.
+
+    let #1 = a => b, #2 = #1 * #1 in #2 => #2
+
+Bindings are written in the order they are needed, so each one refers only to those before it.
+*/
+
+use std::collections::HashMap;
 use std::io;
 use std::sync::Arc;
 // This module depends on the term module and on the term writer module.
 use super::terms::*;
-use super::util::TermWriter;
+use super::util::{TermWriter, WriterConfig, Parens};
+use super::util::{precedence, LAMBDA, MAP, PRODUCT, PRIMARY};
 use super::termfactory::TermFactory;
-use std::ops::Deref;
-use util::escape;
+use super::locus::Locus;
+use util::escape_with;
+use util::pretty::Doc;
 
-/// Write a term in relision form.
-pub struct EliWriter {}
+/// Write a term in relision form.  Terms that do not fit in the line width are broken after
+/// `=>`, `*`, and `->`, and the continuation lines are indented.
+pub struct EliWriter {
+    /// The options shared by all writers.
+    config: WriterConfig,
+    /// The line width to fit terms into.
+    width: usize,
+    /// The number of spaces to indent continuation lines.
+    indent: usize,
+}
 
-impl TermWriter for EliWriter {
-    fn new() -> Self {
-        EliWriter {}
+impl EliWriter {
+    /// Set the line width.  Use `usize::MAX` to write every term on a single line.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
     }
 
-    fn print(&self, fact: &TermFactory, term: &Arc<Term>) {
-        self.write(&mut io::stdout(), fact, term).unwrap();
+    /// Set the number of spaces continuation lines are indented.
+    pub fn with_indent(mut self, indent: usize) -> Self {
+        self.indent = indent;
+        self
     }
 
-    fn println(&self, fact: &TermFactory, term: &Arc<Term>) {
-        self.write(&mut io::stdout(), fact, term).unwrap();
-        println!("");
+    /// Build the document for a term, with bindings for shared subterms if so configured.
+    fn document(&self, fact: &TermFactory, term: &Arc<Term>) -> Doc {
+        let mut layout = Layout { writer: self, fact, names: HashMap::new() };
+        if !self.config.sharing {
+            return layout.doc(term);
+        }
+        let mut counts = HashMap::new();
+        let mut order = vec![];
+        layout.count(term, &mut counts, &mut order);
+        let shared: Vec<Arc<Term>> = order.into_iter()
+            .filter(|term| counts[&(&**term as *const Term)] > 1)
+            .collect();
+        if shared.is_empty() {
+            return layout.doc(term);
+        }
+        for (index, term) in shared.iter().enumerate() {
+            layout.names.insert(&**term as *const Term, index + 1);
+        }
+        let mut bindings = vec![];
+        for (index, term) in shared.iter().enumerate() {
+            if index > 0 {
+                bindings.push(Doc::text(","));
+            }
+            bindings.push(Doc::line());
+            bindings.push(Doc::text(format!("#{} = ", index + 1)));
+            bindings.push(layout.definition(term));
+        }
+        Doc::group(Doc::concat(vec![
+            Doc::text("let"),
+            Doc::nest(self.indent, Doc::concat(bindings)),
+            Doc::line(),
+            Doc::text("in"),
+            Doc::nest(self.indent, Doc::concat(vec![Doc::line(), layout.doc(term)])),
+        ]))
     }
+}
 
-    fn write(&self, dest: &mut io::Write, fact: &TermFactory, term: &Arc<Term>) -> io::Result<()> {
-        match term.deref() {
-            // The root term is unique among all terms.
-            &Term::Root => write!(dest, "^ROOT"),
-
-            // Print a symbol literal.  If this is a known root type, just print it.
-            // If the name is the same as a known root type, then print the type.
-            // Otherwise only print the type if it is not SYMBOL.
-            &Term::SymbolLiteral { ref typ, ref value, .. } => {
-                // A symbol literal might denote a known term, or it might be a
-                // simple symbol.
-                let (mut escaped,modified) = escape(value, '`');
-                if modified {
-                    escaped = format!("`{}`", escaped);
+/// The state of a single write.
+struct Layout<'a> {
+    writer: &'a EliWriter,
+    fact: &'a TermFactory,
+    /// The number of the binding for each shared subterm.
+    names: HashMap<*const Term, usize>,
+}
+
+impl<'a> Layout<'a> {
+    /// Determine if a term is large enough to be worth a binding when it is shared.
+    fn bindable(&self, term: &Term) -> bool {
+        match *term {
+            Term::StaticMap { .. } | Term::StaticProduct { .. } | Term::Lambda { .. } => true,
+            Term::Variable { ref guard, .. } => !self.is_default_guard(guard),
+            _ => false,
+        }
+    }
+
+    /// Get the subterms of a term that are written.
+    fn children<'t>(&self, term: &'t Arc<Term>) -> Vec<&'t Arc<Term>> {
+        let shows_type = self.writer.config.shows_type(self.fact, term);
+        let mut children = vec![];
+        match **term {
+            Term::Root => {}
+            Term::SymbolLiteral { ref typ, .. } |
+            Term::StringLiteral { ref typ, .. } |
+            Term::BooleanLiteral { ref typ, .. } => {
+                if shows_type {
+                    children.push(typ);
                 }
-                if fact.is_named_root_term(value) {
-                    try!(write!(dest, "{}", escaped));
-                    if typ.deref() != fact.get_root().deref() {
-                        // Must print the type.
-                        try!(write!(dest, ": "));
-                        self.write(dest, fact, typ)
-                    } else {
-                        Ok(())
-                    }
-                } else {
-                    // This is not a named root term.  See if it's type is SYMBOL.
-                    try!(write!(dest, "{}", escaped));
-                    if typ.deref() != fact.get_symbol().deref() {
-                        // Must print the type.
-                        try!(write!(dest, ": "));
-                        self.write(dest, fact, typ)
-                    } else {
-                        Ok(())
-                    }
+            }
+            Term::Variable { ref typ, ref guard, .. } => {
+                if !self.is_default_guard(guard) {
+                    children.push(guard);
                 }
-            },
-
-            &Term::StringLiteral { ref typ, ref value, .. } => {
-                let (escaped,_) = escape(value, '"');
-                write!(dest, "\"{}\"", escaped).unwrap();
-                if typ.deref() != fact.get_string().deref() {
-                    try!(write!(dest, ": "));
-                    self.write(dest, fact, typ)
-                } else {
-                    Ok(())
+                if shows_type {
+                    children.push(typ);
                 }
-            },
-
-            &Term::BooleanLiteral { ref typ, ref value, .. } => {
-                write!(dest, "{:?}", value).unwrap();
-                if typ.deref() != fact.get_boolean().deref() {
-                    try!(write!(dest, ": "));
-                    self.write(dest, fact, typ)
-                } else {
-                    Ok(())
+            }
+            Term::StaticMap { ref domain, ref codomain, .. } => {
+                children.push(domain);
+                children.push(codomain);
+            }
+            Term::StaticProduct { ref lhs, ref rhs, .. } => {
+                children.push(lhs);
+                children.push(rhs);
+            }
+            Term::Lambda { ref param, ref body, ref guard, .. } => {
+                children.push(param);
+                if !self.is_default_guard(guard) {
+                    children.push(guard);
                 }
-            },
+                children.push(body);
+            }
+        }
+        children
+    }
 
-            &Term::Variable { ref typ, ref name, ref guard, .. } => {
-                let (escaped,modified) = escape(name, '`');
-                if modified {
-                    try!(write!(dest, "$`{}`", escaped));
+    /// Count how many times each bindable subterm is written, and list them in the order they
+    /// are first finished, so that every subterm comes after the subterms it contains.  The
+    /// subterms of a bindable term are only visited the first time it is seen.
+    fn count(&self, term: &Arc<Term>, counts: &mut HashMap<*const Term, usize>,
+        order: &mut Vec<Arc<Term>>) {
+        let bindable = self.bindable(term);
+        if bindable {
+            let count = counts.entry(&**term as *const Term).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                return;
+            }
+        }
+        for child in self.children(term) {
+            self.count(child, counts, order);
+        }
+        if bindable {
+            order.push(term.clone());
+        }
+    }
+
+    /// Determine if a guard is the default, which is not written.
+    fn is_default_guard(&self, guard: &Arc<Term>) -> bool {
+        match **guard {
+            Term::BooleanLiteral { ref typ, value: true, .. } => *typ == self.fact.get_boolean(),
+            _ => false,
+        }
+    }
+
+    /// Get the name of a shared subterm.
+    fn name(&self, term: &Arc<Term>) -> Option<usize> {
+        self.names.get(&(&**term as *const Term)).cloned()
+    }
+
+    /// Build the document for a term, preceded by its locus if loci are written.  A shared
+    /// subterm is written as a reference to its binding.
+    fn doc(&self, term: &Arc<Term>) -> Doc {
+        match self.name(term) {
+            Some(name) => Doc::text(format!("#{}", name)),
+            None => self.definition(term),
+        }
+    }
+
+    /// Build the document for a term, even if it is shared.
+    fn definition(&self, term: &Arc<Term>) -> Doc {
+        let doc = self.bare(term);
+        if self.writer.config.loci {
+            let locus = self.fact.get_locus(term);
+            if locus != Locus::Internal {
+                return Doc::concat(vec![Doc::text(format!("/* {} */ ", locus)), doc]);
+            }
+        }
+        doc
+    }
+
+    /// Build the document for a term without its locus.
+    fn bare(&self, term: &Arc<Term>) -> Doc {
+        let config = &self.writer.config;
+        match **term {
+            // The root term is unique among all terms.
+            Term::Root => Doc::text("^ROOT"),
+
+            // A symbol literal might denote a known term, or it might be a simple symbol.
+            Term::SymbolLiteral { ref value, .. } => {
+                let name = quote_name(value, config.ascii);
+                self.typed(Doc::text(name), term)
+            }
+
+            Term::StringLiteral { ref value, .. } => {
+                let (escaped, _) = escape_with(value, '"', config.ascii);
+                self.typed(Doc::text(format!("\"{}\"", escaped)), term)
+            }
+
+            Term::BooleanLiteral { ref value, .. } => {
+                self.typed(Doc::text(format!("{:?}", value)), term)
+            }
+
+            Term::Variable { ref name, ref guard, .. } => {
+                let (escaped, modified) = escape_with(name, '`', config.ascii);
+                let plain = !name.is_empty() &&
+                    name.chars().all(|ch| ch.is_alphanumeric() || ch == '_');
+                let name = if modified || !plain {
+                    format!("$`{}`", escaped)
                 } else {
-                    try!(write!(dest, "${}", escaped));
-                }
-                match guard.deref() {
-                    &Term::BooleanLiteral { ref typ, value, .. } => {
-                        if typ.deref() != fact.get_boolean().deref() || value != true {
-                            try!(write!(dest, "{{"));
-                            try!(self.write(dest, fact, guard));
-                            try!(write!(dest, "}}"));
+                    format!("${}", escaped)
+                };
+                let head = Doc::concat(vec![Doc::text(name), self.guard(guard)]);
+                self.typed(head, term)
+            }
+
+            // Unless every subterm is parenthesized, chains of maps are written together, so
+            // that they break at every arrow or not at all.  Maps associate to the right.
+            Term::StaticMap { ref domain, ref codomain, .. } => {
+                let mut parts = vec![self.operand(domain, PRODUCT)];
+                let mut rest = codomain;
+                if config.parens == Parens::Minimal {
+                    while let Term::StaticMap { ref domain, ref codomain, .. } = **rest {
+                        if self.name(rest).is_some() {
+                            break;
                         }
-                    }
-                    _ => {
-                        try!(write!(dest, "{{"));
-                        try!(self.write(dest, fact, guard));
-                        try!(write!(dest, "}}"));
+                        parts.push(Doc::text(" =>"));
+                        parts.push(Doc::line());
+                        parts.push(self.operand(domain, PRODUCT));
+                        rest = codomain;
                     }
                 }
-                if typ.deref() != fact.get_any().deref() {
-                    try!(write!(dest, ": "));
-                    self.write(dest, fact, typ)
-                } else {
-                    Ok(())
-                }
-            },
-
-            &Term::StaticMap { ref domain, ref codomain, .. } => {
-                try!(self.write(dest, fact, domain));
-                try!(write!(dest, " => "));
-                self.write(dest, fact, codomain)
-            },
-
-            &Term::StaticProduct { ref lhs, ref rhs, .. } => {
-                try!(self.write(dest, fact, lhs));
-                try!(write!(dest, " * "));
-                self.write(dest, fact, rhs)
-            },
-
-            &Term::Lambda { ref param, ref body, ref guard, .. } => {
-                try!(self.write(dest, fact, param));
-                try!(write!(dest, " ->"));
-                match guard.deref() {
-                    &Term::BooleanLiteral { ref typ, value, .. } => {
-                        if typ.deref() != fact.get_boolean().deref() || value != true {
-                            try!(write!(dest, "{{"));
-                            try!(self.write(dest, fact, guard));
-                            try!(write!(dest, "}}"));
+                parts.push(Doc::text(" =>"));
+                parts.push(Doc::line());
+                parts.push(self.operand(rest, MAP));
+                self.chain(parts)
+            }
+
+            // Chains of products are written together, too.  Products associate to the left.
+            Term::StaticProduct { ref lhs, ref rhs, .. } => {
+                let mut rights = vec![rhs];
+                let mut first = lhs;
+                if config.parens == Parens::Minimal {
+                    while let Term::StaticProduct { ref lhs, ref rhs, .. } = **first {
+                        if self.name(first).is_some() {
+                            break;
                         }
-                    }
-                    _ => {
-                        try!(write!(dest, "{{"));
-                        try!(self.write(dest, fact, guard));
-                        try!(write!(dest, "}}"));
+                        rights.push(rhs);
+                        first = lhs;
                     }
                 }
-                try!(write!(dest, " "));
-                self.write(dest, fact, body)
-            },
+                let mut parts = vec![self.operand(first, PRODUCT)];
+                for rhs in rights.into_iter().rev() {
+                    parts.push(Doc::text(" *"));
+                    parts.push(Doc::line());
+                    parts.push(self.operand(rhs, PRIMARY));
+                }
+                self.chain(parts)
+            }
+
+            Term::Lambda { ref param, ref body, ref guard, .. } => {
+                self.chain(vec![
+                    self.operand(param, MAP),
+                    Doc::text(" ->"),
+                    self.guard(guard),
+                    Doc::line(),
+                    self.operand(body, LAMBDA),
+                ])
+            }
         }
     }
+
+    /// Build the document for a term that is part of a map, product, lambda, or type, where
+    /// only terms that bind at least as tightly as `level` are allowed.  Parentheses are added
+    /// if the term binds more loosely, or if every map, product, and lambda is parenthesized.
+    /// References to shared subterms never need parentheses.
+    fn operand(&self, term: &Arc<Term>, level: u8) -> Doc {
+        let precedence = if self.name(term).is_some() { PRIMARY } else { precedence(term) };
+        let parens = match self.writer.config.parens {
+            Parens::Full => precedence < PRIMARY,
+            Parens::Minimal => precedence < level,
+        };
+        if parens {
+            Doc::concat(vec![Doc::text("("), self.doc(term), Doc::text(")")])
+        } else {
+            self.doc(term)
+        }
+    }
+
+    /// Group the parts of a chain, indenting every part after the first when it is broken.
+    fn chain(&self, mut parts: Vec<Doc>) -> Doc {
+        let first = parts.remove(0);
+        Doc::group(Doc::concat(vec![first, Doc::nest(self.writer.indent, Doc::concat(parts))]))
+    }
+
+    /// Add the type of a term to its document if the configuration calls for it.
+    fn typed(&self, doc: Doc, term: &Arc<Term>) -> Doc {
+        if !self.writer.config.shows_type(self.fact, term) {
+            return doc;
+        }
+        let typ = self.fact.get_type(term);
+        Doc::concat(vec![doc, Doc::text(": "), self.operand(&typ, PRIMARY)])
+    }
+
+    /// Build the document for a guard.  A guard that is the Boolean literal true is left out.
+    fn guard(&self, guard: &Arc<Term>) -> Doc {
+        if self.is_default_guard(guard) {
+            return Doc::Nil;
+        }
+        Doc::group(Doc::concat(vec![
+            Doc::text("{"),
+            Doc::nest(self.writer.indent, Doc::concat(vec![Doc::softline(), self.doc(guard)])),
+            Doc::softline(),
+            Doc::text("}"),
+        ]))
+    }
+}
+
+/// Write a name so that the parser will read it back as the same name.  Names that are not
+/// plain identifiers, or that are keywords, are enclosed in backticks.
+pub fn quote_name(name: &str, ascii: bool) -> String {
+    const KEYWORDS: &[&str] = &["root", "operator", "rule", "enable", "disable", "set", "load",
+        "in", "let", "true", "false"];
+    let (escaped, modified) = escape_with(name, '`', ascii);
+    let mut chars = name.chars();
+    let plain = match chars.next() {
+        Some(first) => first.is_alphabetic() || first == '_',
+        None => false,
+    } && chars.all(|ch| ch.is_alphanumeric() || ch == '_');
+    if !modified && plain && !KEYWORDS.contains(&name) {
+        escaped
+    } else {
+        format!("`{}`", escaped)
+    }
+}
+
+impl TermWriter for EliWriter {
+    fn new() -> Self {
+        EliWriter { config: WriterConfig::new(), width: 80, indent: 4 }
+    }
+
+    fn config(&self) -> &WriterConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: WriterConfig) {
+        self.config = config;
+    }
+
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        write!(dest, "{}", self.document(fact, term).render(self.width))
+    }
 }
@@ -0,0 +1,521 @@
+//! Write and read terms as JSON.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+Every term is written as a JSON object tagged by its kind.  The members are the following.
+
+    kind        One of root, symbol, string, boolean, variable, static_map, static_product,
+                or lambda.
+    type        The type of the term, for literals and variables, if the writer is configured
+                to write it.  A missing type is the default type for that kind of term.  The
+                type of the other terms is determined by their children, so it is not written.
+    value       The value of a literal, or the name of a variable.
+    children    The subterms, in order.  A variable has its guard, a static map has its domain
+                and codomain, a static product has its left and right terms, and a lambda has
+                its parameter, body, and guard.
+    locus       Where the term was declared.  This is only written if the writer is configured
+                to write loci, and never for an internal locus.  It is an object with line and
+                column members, and a file member for terms from a file.
+
+Characters outside of ASCII are escaped unless the writer is configured for Unicode escapes.
+For example, the variable $x: STRING is written as follows when every type is written.
+
+    {"kind":"variable","type":{"kind":"symbol","type":{"kind":"root"},"value":"STRING"},
+     "value":"x","children":[{"kind":"boolean","type":{"kind":"symbol",...},"value":true}]}
+*/
+
+use std::fmt::Write as FmtWrite;
+use std::io;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::util::{TermWriter, WriterConfig};
+use diagnostics::Diagnostic;
+use error::{Error, Result};
+use terms::locus::Locus;
+
+/// Write a term as JSON.
+pub struct JsonWriter {
+    /// The options shared by all writers.
+    config: WriterConfig,
+}
+
+impl JsonWriter {
+    /// Choose whether loci are written.
+    pub fn with_loci(mut self, loci: bool) -> Self {
+        self.config.loci = loci;
+        self
+    }
+
+    /// Write the members common to many kinds of term.
+    fn head(&self, dest: &mut dyn io::Write, fact: &TermFactory, kind: &str, locus: &Locus,
+        term: &Arc<Term>) -> io::Result<()> {
+        write!(dest, "{{\"kind\":\"{}\"", kind)?;
+        if self.config.shows_type(fact, term) {
+            write!(dest, ",\"type\":")?;
+            self.write(dest, fact, &fact.get_type(term))?;
+        }
+        if self.config.loci {
+            match *locus {
+                Locus::Internal => {}
+                Locus::Console(line, column) => {
+                    write!(dest, ",\"locus\":{{\"line\":{},\"column\":{}}}", line, column)?;
+                }
+                Locus::File(ref name, line, column) => {
+                    write!(dest, ",\"locus\":{{\"file\":{},\"line\":{},\"column\":{}}}",
+                        quote(name, self.config.ascii), line, column)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the children member and close the object.
+    fn children(&self, dest: &mut dyn io::Write, fact: &TermFactory, children: &[&Arc<Term>])
+        -> io::Result<()> {
+        write!(dest, ",\"children\":[")?;
+        for (index, child) in children.iter().enumerate() {
+            if index > 0 {
+                write!(dest, ",")?;
+            }
+            self.write(dest, fact, child)?;
+        }
+        write!(dest, "]}}")
+    }
+}
+
+impl TermWriter for JsonWriter {
+    fn new() -> Self {
+        JsonWriter { config: WriterConfig::new() }
+    }
+
+    fn config(&self) -> &WriterConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: WriterConfig) {
+        self.config = config;
+    }
+
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        let ascii = self.config.ascii;
+        match **term {
+            Term::Root => write!(dest, "{{\"kind\":\"root\"}}"),
+            Term::SymbolLiteral { ref locus, ref value, .. } => {
+                self.head(dest, fact, "symbol", locus, term)?;
+                write!(dest, ",\"value\":{}}}", quote(value, ascii))
+            }
+            Term::StringLiteral { ref locus, ref value, .. } => {
+                self.head(dest, fact, "string", locus, term)?;
+                write!(dest, ",\"value\":{}}}", quote(value, ascii))
+            }
+            Term::BooleanLiteral { ref locus, value, .. } => {
+                self.head(dest, fact, "boolean", locus, term)?;
+                write!(dest, ",\"value\":{}}}", value)
+            }
+            Term::Variable { ref locus, ref name, ref guard, .. } => {
+                self.head(dest, fact, "variable", locus, term)?;
+                write!(dest, ",\"value\":{}", quote(name, ascii))?;
+                self.children(dest, fact, &[guard])
+            }
+            Term::StaticMap { ref locus, ref domain, ref codomain } => {
+                self.head(dest, fact, "static_map", locus, term)?;
+                self.children(dest, fact, &[domain, codomain])
+            }
+            Term::StaticProduct { ref locus, ref lhs, ref rhs } => {
+                self.head(dest, fact, "static_product", locus, term)?;
+                self.children(dest, fact, &[lhs, rhs])
+            }
+            Term::Lambda { ref locus, ref param, ref body, ref guard } => {
+                self.head(dest, fact, "lambda", locus, term)?;
+                self.children(dest, fact, &[param, body, guard])
+            }
+        }
+    }
+}
+
+/// Make a JSON string literal.  If `ascii` is set, characters outside of ASCII are escaped.
+fn quote(text: &str, ascii: bool) -> String {
+    let mut out = String::from("\"");
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", ch as u32);
+            }
+            ch if ascii && !ch.is_ascii() => {
+                let mut units = [0; 2];
+                for unit in ch.encode_utf16(&mut units).iter() {
+                    let _ = write!(out, "\\u{:04x}", unit);
+                }
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// A JSON value.
+#[derive(Debug)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Get a member of an object.
+    fn get(&self, name: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref members) => {
+                members.iter().find(|(key, _)| key == name).map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Read terms written by a `JsonWriter`.
+pub struct JsonReader<'a> {
+    /// The factory used to make terms.
+    fact: &'a TermFactory,
+}
+
+impl<'a> JsonReader<'a> {
+    /// Read a term written by a `JsonWriter`.  The whole source is read.
+    pub fn read(src: &mut dyn io::Read, fact: &TermFactory) -> Result<Arc<Term>> {
+        let mut text = String::new();
+        src.read_to_string(&mut text)?;
+        JsonReader::read_str(&text, fact)
+    }
+
+    /// Read a term from JSON text.
+    pub fn read_str(text: &str, fact: &TermFactory) -> Result<Arc<Term>> {
+        let mut parser = JsonParser { chars: text.chars().collect(), pos: 0 };
+        parser.skip();
+        let value = parser.value()?;
+        parser.skip();
+        if parser.pos != parser.chars.len() {
+            return Err(parser.error("unexpected text after the JSON value"));
+        }
+        JsonReader { fact }.term(&value)
+    }
+
+    /// Convert a JSON value to a term.
+    fn term(&self, json: &Json) -> Result<Arc<Term>> {
+        let fact = self.fact;
+        let kind = match json.get("kind") {
+            Some(Json::Str(kind)) => kind.as_str(),
+            _ => return Err(invalid("every term must have a kind")),
+        };
+        if kind == "root" {
+            return Ok(fact.get_root());
+        }
+        let locus = self.locus(json.get("locus"))?;
+        match kind {
+            "symbol" => {
+                let value = string_member(json, "value")?;
+                let typ = self.typ(json, || if fact.is_named_root_term(&value) {
+                    fact.get_root()
+                } else {
+                    fact.get_symbol()
+                })?;
+                if locus == Locus::Internal && *typ == Term::Root {
+                    if let Some(term) = fact.get_named_root_term(&value) {
+                        return Ok(term);
+                    }
+                }
+                Ok(fact.new_typed_symbol(locus, value, typ))
+            }
+            "string" => {
+                let typ = self.typ(json, || fact.get_string())?;
+                Ok(fact.new_typed_string(locus, string_member(json, "value")?, typ))
+            }
+            "boolean" => {
+                let typ = self.typ(json, || fact.get_boolean())?;
+                let value = match json.get("value") {
+                    Some(&Json::Bool(value)) => value,
+                    _ => return Err(invalid("a boolean must have a true or false value")),
+                };
+                let shared = fact.new_boolean(value);
+                if locus == Locus::Internal && typ == fact.get_type(&shared) {
+                    Ok(shared)
+                } else {
+                    Ok(fact.new_typed_boolean(locus, value, typ))
+                }
+            }
+            "variable" => {
+                let typ = self.typ(json, || fact.get_any())?;
+                let name = string_member(json, "value")?;
+                let children = self.children(json, kind, 1)?;
+                Ok(fact.new_variable(locus, &typ, name, &children[0]))
+            }
+            "static_map" => {
+                let children = self.children(json, kind, 2)?;
+                Ok(fact.new_static_map(locus, &children[0], &children[1]))
+            }
+            "static_product" => {
+                let children = self.children(json, kind, 2)?;
+                Ok(fact.new_static_product(locus, &children[0], &children[1]))
+            }
+            "lambda" => {
+                let children = self.children(json, kind, 3)?;
+                Ok(fact.new_lambda(locus, &children[0], &children[1], &children[2]))
+            }
+            _ => Err(invalid(&format!("unknown kind of term {}", kind))),
+        }
+    }
+
+    /// Read the type member, or make the default type if there is none.
+    fn typ<F: FnOnce() -> Arc<Term>>(&self, json: &Json, default: F) -> Result<Arc<Term>> {
+        match json.get("type") {
+            Some(typ) => self.term(typ),
+            None => Ok(default()),
+        }
+    }
+
+    /// Read the children member, which must have the given number of terms.
+    fn children(&self, json: &Json, kind: &str, count: usize) -> Result<Vec<Arc<Term>>> {
+        match json.get("children") {
+            Some(Json::Array(items)) if items.len() == count => {
+                items.iter().map(|item| self.term(item)).collect()
+            }
+            _ => Err(invalid(&format!("a {} must have {} {}", kind, count,
+                if count == 1 { "child" } else { "children" }))),
+        }
+    }
+
+    /// Read a locus.  A missing locus is internal.
+    fn locus(&self, json: Option<&Json>) -> Result<Locus> {
+        let json = match json {
+            None | Some(Json::Null) => return Ok(Locus::Internal),
+            Some(json) => json,
+        };
+        let line = number_member(json, "line")?;
+        let column = number_member(json, "column")?;
+        match json.get("file") {
+            None => Ok(Locus::Console(line, column)),
+            Some(Json::Str(name)) => Ok(Locus::File(name.clone(), line, column)),
+            Some(_) => Err(invalid("the file of a locus must be a string")),
+        }
+    }
+}
+
+/// Get a string member of an object.
+fn string_member(json: &Json, name: &str) -> Result<String> {
+    match json.get(name) {
+        Some(Json::Str(value)) => Ok(value.clone()),
+        _ => Err(invalid(&format!("the {} must be a string", name))),
+    }
+}
+
+/// Get a line or column member of a locus.
+fn number_member(json: &Json, name: &str) -> Result<u32> {
+    match json.get(name) {
+        Some(&Json::Number(value)) if value >= 0.0 && value <= u32::MAX as f64 &&
+            value.fract() == 0.0 => Ok(value as u32),
+        _ => Err(invalid(&format!("the {} of a locus must be a non-negative integer", name))),
+    }
+}
+
+/// Make an error for JSON that is well-formed but does not describe a term.
+fn invalid(message: &str) -> Error {
+    Error::Parse(Box::new(Diagnostic::error(message)))
+}
+
+/// A small parser for JSON text.
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    /// Skip white space.
+    fn skip(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    /// Consume the given text, or fail.
+    fn expect(&mut self, text: &str) -> Result<()> {
+        for ch in text.chars() {
+            if self.chars.get(self.pos) != Some(&ch) {
+                return Err(self.error(&format!("expected {}", text)));
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    /// Parse a value.  White space before the value has been skipped.
+    fn value(&mut self) -> Result<Json> {
+        match self.chars.get(self.pos) {
+            Some(&'n') => self.expect("null").map(|_| Json::Null),
+            Some(&'t') => self.expect("true").map(|_| Json::Bool(true)),
+            Some(&'f') => self.expect("false").map(|_| Json::Bool(false)),
+            Some(&'"') => self.string().map(Json::Str),
+            Some(&'[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.skip();
+                if self.chars.get(self.pos) == Some(&']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    self.skip();
+                    items.push(self.value()?);
+                    self.skip();
+                    match self.chars.get(self.pos) {
+                        Some(&',') => self.pos += 1,
+                        Some(&']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected , or ]")),
+                    }
+                }
+            }
+            Some(&'{') => {
+                self.pos += 1;
+                let mut members = vec![];
+                self.skip();
+                if self.chars.get(self.pos) == Some(&'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                loop {
+                    self.skip();
+                    if self.chars.get(self.pos) != Some(&'"') {
+                        return Err(self.error("expected a member name"));
+                    }
+                    let name = self.string()?;
+                    self.skip();
+                    self.expect(":")?;
+                    self.skip();
+                    members.push((name, self.value()?));
+                    self.skip();
+                    match self.chars.get(self.pos) {
+                        Some(&',') => self.pos += 1,
+                        Some(&'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(members));
+                        }
+                        _ => return Err(self.error("expected , or }")),
+                    }
+                }
+            }
+            Some(&ch) if ch == '-' || ch.is_ascii_digit() => {
+                let start = self.pos;
+                while let Some(&ch) = self.chars.get(self.pos) {
+                    if !(ch.is_ascii_digit() || "+-.eE".contains(ch)) {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                match text.parse() {
+                    Ok(value) => Ok(Json::Number(value)),
+                    Err(_) => Err(self.error("invalid number")),
+                }
+            }
+            _ => Err(self.error("expected a JSON value")),
+        }
+    }
+
+    /// Parse a string, including the quotation marks.
+    fn string(&mut self) -> Result<String> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            let ch = match self.chars.get(self.pos) {
+                Some(&ch) => ch,
+                None => return Err(self.error("missing closing quotation mark")),
+            };
+            self.pos += 1;
+            match ch {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escaped = self.chars.get(self.pos).cloned();
+                    self.pos += 1;
+                    match escaped {
+                        Some('"') => text.push('"'),
+                        Some('\\') => text.push('\\'),
+                        Some('/') => text.push('/'),
+                        Some('b') => text.push('\u{8}'),
+                        Some('f') => text.push('\u{c}'),
+                        Some('n') => text.push('\n'),
+                        Some('r') => text.push('\r'),
+                        Some('t') => text.push('\t'),
+                        Some('u') => {
+                            let high = self.hex4()?;
+                            let code = if (0xD800..0xDC00).contains(&high) {
+                                self.expect("\\u")?;
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(self.error("invalid surrogate pair"));
+                                }
+                                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                            } else {
+                                high
+                            };
+                            match ::std::char::from_u32(code) {
+                                Some(ch) => text.push(ch),
+                                None => return Err(self.error("invalid character escape")),
+                            }
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                ch => text.push(ch),
+            }
+        }
+    }
+
+    /// Parse four hexadecimal digits.
+    fn hex4(&mut self) -> Result<u32> {
+        if self.pos + 4 > self.chars.len() {
+            return Err(self.error("expected four hexadecimal digits"));
+        }
+        let digits: String = self.chars[self.pos..self.pos + 4].iter().collect();
+        match u32::from_str_radix(&digits, 16) {
+            Ok(value) => {
+                self.pos += 4;
+                Ok(value)
+            }
+            Err(_) => Err(self.error("expected four hexadecimal digits")),
+        }
+    }
+
+    /// Make an error for the current position.
+    fn error(&self, message: &str) -> Error {
+        Error::Parse(Box::new(Diagnostic::error(message)
+            .with_note(&format!("at character offset {}", self.pos))))
+    }
+}
@@ -0,0 +1,377 @@
+//! Write terms in mathematical notation.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+Terms are written the way they would be typeset in a paper, either as LaTeX math (without the
+surrounding dollar signs) or as plain Unicode text.
+
+    Term            Unicode         LaTeX
+    A => B          A → B           A \to B
+    A * B           A × B           A \times B
+    $x -> body      λ𝑥. body        \lambda x.\, body
+    $x              𝑥               x
+    sym             sym             \mathtt{sym}
+    STRING          STRING          \mathsf{STRING}
+
+Variables are in italics.  Guards are not written in place.  Instead they are collected, in the
+order they are found, and written after the term as side conditions.
+
+    $x{p} -> $x     λ𝑥. 𝑥   if p    \lambda x.\, x \quad\text{if } \mathtt{p}
+
+A symbol that names an operator can be given its own notation, such as + or \oplus, which is
+written in place of the symbol.
+
+The precedence of the operators is the same as in the ELI form, so parentheses are written in
+the same places.  The writer configuration controls types, parentheses, and the escaping of
+text; loci and sharing cannot be shown.
+*/
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::operators::OperatorLibrary;
+use super::util::{TermWriter, WriterConfig, Parens};
+use super::util::{precedence, LAMBDA, MAP, PRODUCT, PRIMARY};
+use super::eli_writer::quote_name;
+use util::escape_with;
+
+/// The notations that can be written.
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    Latex,
+    Unicode,
+}
+
+/// Write a term as LaTeX math.
+pub struct LatexWriter {
+    /// The options shared by all writers.
+    config: WriterConfig,
+    /// The notation for operators, by name.
+    notations: HashMap<String, String>,
+}
+
+/// Write a term in Unicode mathematical notation.  Since the notation is not ASCII anyway, text
+/// is not escaped to ASCII unless the writer is configured to.
+pub struct UnicodeWriter {
+    /// The options shared by all writers.
+    config: WriterConfig,
+    /// The notation for operators, by name.
+    notations: HashMap<String, String>,
+}
+
+impl LatexWriter {
+    /// Write a symbol that names the given operator with the given LaTeX, such as `\oplus`.
+    pub fn with_notation(mut self, operator: &str, notation: &str) -> Self {
+        self.notations.insert(operator.to_string(), notation.to_string());
+        self
+    }
+
+    /// Write every declared operator that does not already have a notation as an upright
+    /// operator name, such as `\operatorname{succ}`.
+    pub fn with_operators(mut self, operators: &OperatorLibrary) -> Self {
+        for operator in operators.iter() {
+            let notation = format!("\\operatorname{{{}}}", latex_math(&operator.name));
+            self.notations.entry(operator.name.clone()).or_insert(notation);
+        }
+        self
+    }
+}
+
+impl UnicodeWriter {
+    /// Write a symbol that names the given operator with the given text, such as `⊕`.
+    pub fn with_notation(mut self, operator: &str, notation: &str) -> Self {
+        self.notations.insert(operator.to_string(), notation.to_string());
+        self
+    }
+
+    /// Write every declared operator that does not already have a notation in upright
+    /// sans-serif letters, such as `𝗌𝗎𝖼𝖼`.
+    pub fn with_operators(mut self, operators: &OperatorLibrary) -> Self {
+        for operator in operators.iter() {
+            let notation = operator.name.chars().map(sans_serif).collect();
+            self.notations.entry(operator.name.clone()).or_insert(notation);
+        }
+        self
+    }
+}
+
+impl TermWriter for LatexWriter {
+    fn new() -> Self {
+        LatexWriter { config: WriterConfig::new(), notations: HashMap::new() }
+    }
+
+    fn config(&self) -> &WriterConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: WriterConfig) {
+        self.config = config;
+    }
+
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        let math = Math { target: Target::Latex, config: &self.config,
+            notations: &self.notations, fact };
+        write!(dest, "{}", math.render(term))
+    }
+}
+
+impl TermWriter for UnicodeWriter {
+    fn new() -> Self {
+        UnicodeWriter {
+            config: WriterConfig { ascii: false, ..WriterConfig::new() },
+            notations: HashMap::new(),
+        }
+    }
+
+    fn config(&self) -> &WriterConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: WriterConfig) {
+        self.config = config;
+    }
+
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        let math = Math { target: Target::Unicode, config: &self.config,
+            notations: &self.notations, fact };
+        write!(dest, "{}", math.render(term))
+    }
+}
+
+/// The state of a single write.
+struct Math<'a> {
+    target: Target,
+    config: &'a WriterConfig,
+    notations: &'a HashMap<String, String>,
+    fact: &'a TermFactory,
+}
+
+impl<'a> Math<'a> {
+    /// Write a term followed by its side conditions.
+    fn render(&self, term: &Arc<Term>) -> String {
+        let mut conditions = vec![];
+        let mut out = self.term(term, &mut conditions);
+        // Guards can have guards of their own, which are added to the end as they are written.
+        let mut index = 0;
+        let mut written = vec![];
+        while index < conditions.len() {
+            let guard = conditions[index].clone();
+            written.push(self.term(&guard, &mut conditions));
+            index += 1;
+        }
+        if !written.is_empty() {
+            match self.target {
+                Target::Latex => {
+                    out.push_str(" \\quad\\text{if } ");
+                    out.push_str(&written.join(",\\ "));
+                }
+                Target::Unicode => {
+                    out.push_str("   if ");
+                    out.push_str(&written.join(", "));
+                }
+            }
+        }
+        out
+    }
+
+    /// Write a term, adding any guards to the side conditions.
+    fn term(&self, term: &Arc<Term>, conditions: &mut Vec<Arc<Term>>) -> String {
+        let latex = self.target == Target::Latex;
+        match **term {
+            Term::Root => {
+                if latex { "{}^\\wedge\\mathsf{ROOT}".to_string() } else { "^ROOT".to_string() }
+            }
+            Term::SymbolLiteral { ref typ, ref value, .. } => {
+                let text = if let Some(notation) = self.notations.get(value) {
+                    notation.clone()
+                } else if **typ == Term::Root && self.fact.is_named_root_term(value) {
+                    if latex {
+                        format!("\\mathsf{{{}}}", latex_math(&self.escape(value, '`')))
+                    } else {
+                        quote_name(value, self.config.ascii)
+                    }
+                } else if latex {
+                    format!("\\mathtt{{{}}}", latex_math(&self.escape(value, '`')))
+                } else {
+                    quote_name(value, self.config.ascii)
+                };
+                self.typed(text, term, conditions)
+            }
+            Term::StringLiteral { ref value, .. } => {
+                let escaped = self.escape(value, '"');
+                let text = if latex {
+                    format!("\\text{{``{}''}}", latex_text(&escaped))
+                } else {
+                    format!("\u{201c}{}\u{201d}", escaped)
+                };
+                self.typed(text, term, conditions)
+            }
+            Term::BooleanLiteral { value, .. } => {
+                let text = if latex { format!("\\mathbf{{{}}}", value) } else { value.to_string() };
+                self.typed(text, term, conditions)
+            }
+            Term::Variable { ref name, ref guard, .. } => {
+                self.guard(guard, conditions);
+                let text = self.variable(name);
+                self.typed(text, term, conditions)
+            }
+            Term::StaticMap { ref domain, ref codomain, .. } => {
+                let arrow = if latex { " \\to " } else { " \u{2192} " };
+                format!("{}{}{}", self.operand(domain, PRODUCT, conditions), arrow,
+                    self.operand(codomain, MAP, conditions))
+            }
+            Term::StaticProduct { ref lhs, ref rhs, .. } => {
+                let times = if latex { " \\times " } else { " \u{d7} " };
+                format!("{}{}{}", self.operand(lhs, PRODUCT, conditions), times,
+                    self.operand(rhs, PRIMARY, conditions))
+            }
+            Term::Lambda { ref param, ref body, ref guard, .. } => {
+                let param = self.operand(param, MAP, conditions);
+                self.guard(guard, conditions);
+                let body = self.operand(body, LAMBDA, conditions);
+                if latex {
+                    format!("\\lambda {}.\\, {}", param, body)
+                } else {
+                    format!("\u{3bb}{}. {}", param, body)
+                }
+            }
+        }
+    }
+
+    /// Write a term where only terms that bind at least as tightly as `level` are allowed,
+    /// adding parentheses if needed.
+    fn operand(&self, term: &Arc<Term>, level: u8, conditions: &mut Vec<Arc<Term>>) -> String {
+        let precedence = precedence(term);
+        let parens = match self.config.parens {
+            Parens::Full => precedence < PRIMARY,
+            Parens::Minimal => precedence < level,
+        };
+        let text = self.term(term, conditions);
+        if parens { format!("({})", text) } else { text }
+    }
+
+    /// Add the type of a term if the configuration calls for it.
+    fn typed(&self, text: String, term: &Arc<Term>, conditions: &mut Vec<Arc<Term>>) -> String {
+        if !self.config.shows_type(self.fact, term) {
+            return text;
+        }
+        let typ = self.operand(&self.fact.get_type(term), PRIMARY, conditions);
+        match self.target {
+            Target::Latex => format!("{} \\mathbin{{:}} {}", text, typ),
+            Target::Unicode => format!("{}: {}", text, typ),
+        }
+    }
+
+    /// Add a guard to the side conditions, unless it is the Boolean literal true.
+    fn guard(&self, guard: &Arc<Term>, conditions: &mut Vec<Arc<Term>>) {
+        if let Term::BooleanLiteral { ref typ, value: true, .. } = **guard {
+            if *typ == self.fact.get_boolean() {
+                return;
+            }
+        }
+        conditions.push(guard.clone());
+    }
+
+    /// Write a variable name in italics.
+    fn variable(&self, name: &str) -> String {
+        let escaped = self.escape(name, '`');
+        match self.target {
+            Target::Latex => {
+                let mut chars = escaped.chars();
+                let letter = chars.next().is_some_and(|ch| ch.is_ascii_alphabetic());
+                if letter && chars.next().is_none() {
+                    escaped
+                } else {
+                    format!("\\mathit{{{}}}", latex_math(&escaped))
+                }
+            }
+            Target::Unicode => escaped.chars().map(italic).collect(),
+        }
+    }
+
+    /// Escape text as configured.
+    fn escape(&self, text: &str, border: char) -> String {
+        escape_with(text, border, self.config.ascii).0
+    }
+}
+
+/// Get the mathematical italic form of an ASCII letter.  Other characters are unchanged.
+fn italic(ch: char) -> char {
+    let code = match ch {
+        // The italic small h was encoded long before the rest, as the Planck constant.
+        'h' => 0x210E,
+        'a'..='z' => 0x1D44E + (ch as u32 - 'a' as u32),
+        'A'..='Z' => 0x1D434 + (ch as u32 - 'A' as u32),
+        _ => return ch,
+    };
+    ::std::char::from_u32(code).unwrap_or(ch)
+}
+
+/// Get the mathematical sans-serif form of an ASCII letter or digit.  Other characters are
+/// unchanged.
+fn sans_serif(ch: char) -> char {
+    let code = match ch {
+        'a'..='z' => 0x1D5BA + (ch as u32 - 'a' as u32),
+        'A'..='Z' => 0x1D5A0 + (ch as u32 - 'A' as u32),
+        '0'..='9' => 0x1D7E2 + (ch as u32 - '0' as u32),
+        _ => return ch,
+    };
+    ::std::char::from_u32(code).unwrap_or(ch)
+}
+
+/// Escape the characters that are special in LaTeX math mode.
+fn latex_math(text: &str) -> String {
+    let mut out = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\\' => out.push_str("\\backslash{}"),
+            '^' => out.push_str("\\hat{}"),
+            '~' => out.push_str("\\sim{}"),
+            ' ' => out.push_str("\\ "),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Escape the characters that are special in LaTeX text mode.
+fn latex_text(text: &str) -> String {
+    let mut out = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '{' | '}' | '$' | '&' | '#' | '%' | '_' => {
+                out.push('\\');
+                out.push(ch);
+            }
+            _ => out.push(ch),
+        }
+    }
+    out
+}
@@ -30,13 +30,35 @@ mod terms;          // The different kinds of terms.
 mod termfactory;    // Constructing terms.
 mod util;           // Utilities for working with terms.
 mod eli_writer;     // Write terms in ELI format.
+mod eli_parser;     // Read terms in ELI format.
+mod binary;         // Write and read terms in binary form.
+mod json;           // Write and read terms as JSON.
+mod sexp;           // Write and read terms as S-expressions.
+mod dot_writer;     // Write terms as Graphviz graphs.
+mod math;           // Write terms in mathematical notation.
 mod universe;       // The term universe.
 mod locus;          // The locus.
+mod operators;      // Operator declarations.
+mod rules;          // Rewrite rules.
+mod rewriter;       // Apply rewrite rules to terms.
+mod checker;        // Check the types given in terms.
+mod settings;       // Settings that control a universe.
 
 // Expose the important stuff directly through this module to simplify the
 // interface.
 pub use self::locus::Locus;
 pub use self::terms::Term;
 pub use self::termfactory::TermFactory;
-pub use self::util::TermWriter;
+pub use self::util::{TermWriter, Display, WriterConfig, ShowTypes, Parens};
 pub use self::eli_writer::EliWriter;
+pub use self::eli_parser::{EliParser, Statement};
+pub use self::binary::{BinaryWriter, BinaryReader};
+pub use self::json::{JsonWriter, JsonReader};
+pub use self::sexp::{SexpWriter, SexpReader};
+pub use self::dot_writer::DotWriter;
+pub use self::math::{LatexWriter, UnicodeWriter};
+pub use self::universe::Universe;
+pub use self::operators::{Operator, OperatorLibrary};
+pub use self::rules::{Rule, RuleLibrary, DEFAULT_RULESET};
+pub use self::rewriter::{bind, Rewriter};
+pub use self::settings::{Setting, SettingKind, Settings};
@@ -0,0 +1,92 @@
+//! Declare operators and keep track of them.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use super::terms::*;
+use terms::locus::Locus;
+
+/// An operator declaration.  An operator has a name and a type, which is usually a static map
+/// from the operator's domain to its codomain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operator {
+    /// Where the operator was declared.
+    pub locus: Locus,
+    /// The name of the operator.
+    pub name: String,
+    /// The type of the operator.
+    pub typ: Arc<Term>,
+    /// A short description of the operator.  This may be empty.
+    pub description: String,
+}
+
+/// The operator library holds every operator that has been declared.  Operators are kept in
+/// declaration order so that they can be listed and saved in the order they were given.
+#[derive(Clone, Debug, Default)]
+pub struct OperatorLibrary {
+    /// The operators, in declaration order.
+    operators: Vec<Operator>,
+    /// Map operator names to their position in the list.
+    index: HashMap<String, usize>,
+}
+
+impl OperatorLibrary {
+    /// Make a new, empty operator library.
+    pub fn new() -> Self {
+        OperatorLibrary::default()
+    }
+
+    /// Declare an operator.  If an operator with the same name has already been declared, it
+    /// is replaced and the prior declaration is returned.
+    pub fn declare(&mut self, operator: Operator) -> Option<Operator> {
+        match self.index.get(&operator.name) {
+            Some(&position) => Some(::std::mem::replace(&mut self.operators[position], operator)),
+            None => {
+                self.index.insert(operator.name.clone(), self.operators.len());
+                self.operators.push(operator);
+                None
+            }
+        }
+    }
+
+    /// Get an operator by its name.
+    pub fn get(&self, name: &str) -> Option<&Operator> {
+        self.index.get(name).map(|&position| &self.operators[position])
+    }
+
+    /// Determine if an operator with the given name has been declared.
+    pub fn contains(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    /// Get the number of declared operators.
+    pub fn len(&self) -> usize {
+        self.operators.len()
+    }
+
+    /// Determine if no operators have been declared.
+    pub fn is_empty(&self) -> bool {
+        self.operators.is_empty()
+    }
+
+    /// Iterate over the operators in declaration order.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, Operator> {
+        self.operators.iter()
+    }
+}
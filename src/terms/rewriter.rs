@@ -0,0 +1,304 @@
+//! Rewrite terms with the enabled rules of a universe.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+Rewriting is innermost first.  The parts of a static map or static product are rewritten before
+the term itself, and then the rules are tried on the term in declaration order.  When a rule
+applies, its rewrite is rewritten in the same way, until no rule applies.  Lambdas are values:
+their parts are not rewritten.
+
+A pattern matches a term when the two have the same structure, except that a variable in the
+pattern matches any term of the variable's type (or any term at all if the type is ANY).  A
+variable that occurs more than once must match the same term each time.  The guard of a
+variable and the guard of the rule must rewrite to true once the variables are bound; they are
+only tried after the rest of the pattern matches.  Guards are rewritten by the same rewriter, so
+a guard can need another guard; if guards are nested more than GUARD_DEPTH deep, rewriting
+stops with an error rather than guess whether one holds.
+
+Terms are compared by structure, ignoring their loci.  Every result is remembered by the term it
+came from, so a shared subterm is rewritten only once and stays shared.
+*/
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::rules::{Rule, RuleLibrary};
+use error::{Error, Result};
+
+/// How deeply guards may be nested before rewriting stops.
+const GUARD_DEPTH: usize = 64;
+
+/// The variables bound by matching a pattern, by name.
+type Bindings = Vec<(String, Arc<Term>)>;
+
+/// Rewrite terms with a fixed set of rules.  The limit applies to the total number of rewrites
+/// performed by one rewriter.
+pub struct Rewriter<'a> {
+    /// The factory used to make new terms.
+    fact: &'a TermFactory,
+    /// The rules to try, in order.
+    rules: Vec<&'a Rule>,
+    /// The most rewrites to perform.
+    limit: u64,
+    /// The number of rewrites performed so far.
+    count: u64,
+    /// The result for every term rewritten so far.  The original term is kept so that its
+    /// address is not reused.
+    done: HashMap<*const Term, (Arc<Term>, Arc<Term>)>,
+    /// The number of guards being evaluated.
+    depth: usize,
+}
+
+impl<'a> Rewriter<'a> {
+    /// Make a new rewriter that uses the enabled rules of the library.
+    ///
+    ///   * `fact`: The factory used to make new terms.
+    ///   * `rules`: The rule library.
+    ///   * `limit`: The most rewrites to perform before giving up.
+    ///
+    pub fn new(fact: &'a TermFactory, rules: &'a RuleLibrary, limit: u64) -> Self {
+        Rewriter {
+            fact,
+            rules: rules.enabled_rules(),
+            limit,
+            count: 0,
+            done: HashMap::new(),
+            depth: 0,
+        }
+    }
+
+    /// Get the number of rewrites performed so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Rewrite a term until no rule applies.  If the limit on rewrites is reached, an
+    /// `Error::RewriteLimit` is returned, and if guards are nested too deeply, an
+    /// `Error::GuardDepth`.
+    pub fn rewrite(&mut self, term: &Arc<Term>) -> Result<Arc<Term>> {
+        let key = &**term as *const Term;
+        if let Some((_, result)) = self.done.get(&key) {
+            return Ok(result.clone());
+        }
+        let mut current = self.parts(term)?;
+        while let Some(next) = self.step(&current)? {
+            if self.count >= self.limit {
+                return Err(Error::RewriteLimit(self.limit));
+            }
+            self.count += 1;
+            current = self.parts(&next)?;
+        }
+        self.done.insert(key, (term.clone(), current.clone()));
+        Ok(current)
+    }
+
+    /// Rewrite the parts of a static map or static product.  The term is returned unchanged
+    /// if none of its parts change.
+    fn parts(&mut self, term: &Arc<Term>) -> Result<Arc<Term>> {
+        Ok(match **term {
+            Term::StaticMap { ref locus, ref domain, ref codomain } => {
+                let (new_domain, new_codomain) = (self.rewrite(domain)?, self.rewrite(codomain)?);
+                if Arc::ptr_eq(domain, &new_domain) && Arc::ptr_eq(codomain, &new_codomain) {
+                    term.clone()
+                } else {
+                    self.fact.new_static_map(locus.clone(), &new_domain, &new_codomain)
+                }
+            }
+            Term::StaticProduct { ref locus, ref lhs, ref rhs } => {
+                let (new_lhs, new_rhs) = (self.rewrite(lhs)?, self.rewrite(rhs)?);
+                if Arc::ptr_eq(lhs, &new_lhs) && Arc::ptr_eq(rhs, &new_rhs) {
+                    term.clone()
+                } else {
+                    self.fact.new_static_product(locus.clone(), &new_lhs, &new_rhs)
+                }
+            }
+            _ => term.clone(),
+        })
+    }
+
+    /// Apply the first rule that matches the term, and return the rewrite.  If no rule
+    /// applies, `None` is returned.  Guards are only tried once the whole pattern matches, so
+    /// that a guard is never needed to decide the structure.
+    fn step(&mut self, term: &Arc<Term>) -> Result<Option<Arc<Term>>> {
+        for index in 0..self.rules.len() {
+            let rule = self.rules[index];
+            let (mut bindings, mut guards) = (vec![], vec![]);
+            if !self.matches(&rule.pattern, term, &mut bindings, &mut guards) {
+                continue;
+            }
+            guards.push(rule.guard.clone());
+            let mut holds = true;
+            for guard in &guards {
+                if !self.holds(guard, &bindings)? {
+                    holds = false;
+                    break;
+                }
+            }
+            if holds {
+                return Ok(Some(substitute(self.fact, &rule.rewrite, &bindings,
+                    &mut HashMap::new())));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Match a pattern against a term, adding to the bindings and collecting the guards of the
+    /// variables that are bound.
+    fn matches(&self, pattern: &Arc<Term>, term: &Arc<Term>, bindings: &mut Bindings,
+        guards: &mut Vec<Arc<Term>>) -> bool {
+        match (&**pattern, &**term) {
+            (Term::Variable { typ, name, guard, .. }, _) => {
+                let any = self.fact.get_any();
+                if !is_same(typ, &any) && !is_same(typ, &self.fact.get_type(term)) {
+                    return false;
+                }
+                let bound = bindings.iter().find(|(bound, _)| bound == name)
+                    .map(|(_, value)| value.clone());
+                match bound {
+                    Some(value) => is_same(&value, term),
+                    None => {
+                        bindings.push((name.clone(), term.clone()));
+                        guards.push(guard.clone());
+                        true
+                    }
+                }
+            }
+            (Term::StaticMap { domain: pd, codomain: pc, .. },
+                Term::StaticMap { domain, codomain, .. }) => {
+                self.matches(pd, domain, bindings, guards) &&
+                    self.matches(pc, codomain, bindings, guards)
+            }
+            (Term::StaticProduct { lhs: pl, rhs: pr, .. },
+                Term::StaticProduct { lhs, rhs, .. }) => {
+                self.matches(pl, lhs, bindings, guards) && self.matches(pr, rhs, bindings, guards)
+            }
+            _ => is_same(pattern, term),
+        }
+    }
+
+    /// Decide whether a guard rewrites to true with the given bindings.  Guards nested too
+    /// deeply are an `Error::GuardDepth`.
+    fn holds(&mut self, guard: &Arc<Term>, bindings: &Bindings) -> Result<bool> {
+        if let Term::BooleanLiteral { value: true, .. } = **guard {
+            return Ok(true);
+        }
+        if self.depth >= GUARD_DEPTH {
+            return Err(Error::GuardDepth(GUARD_DEPTH));
+        }
+        let guard = substitute(self.fact, guard, bindings, &mut HashMap::new());
+        self.depth += 1;
+        let result = self.rewrite(&guard);
+        self.depth -= 1;
+        Ok(matches!(*result?, Term::BooleanLiteral { value: true, .. }))
+    }
+}
+
+/// Decide whether two terms have the same structure, ignoring their loci.
+fn is_same(left: &Arc<Term>, right: &Arc<Term>) -> bool {
+    same(left, right, &mut HashSet::new())
+}
+
+/// Compare two terms, remembering the pairs of subterms found to be the same so that shared
+/// subterms are compared only once.
+fn same(left: &Arc<Term>, right: &Arc<Term>, seen: &mut HashSet<(*const Term, *const Term)>)
+    -> bool {
+    if Arc::ptr_eq(left, right) {
+        return true;
+    }
+    let key = (&**left as *const Term, &**right as *const Term);
+    if seen.contains(&key) {
+        return true;
+    }
+    let result = match (&**left, &**right) {
+        (Term::Root, Term::Root) => true,
+        (Term::SymbolLiteral { typ: lt, value: lv, .. },
+            Term::SymbolLiteral { typ: rt, value: rv, .. }) |
+        (Term::StringLiteral { typ: lt, value: lv, .. },
+            Term::StringLiteral { typ: rt, value: rv, .. }) => {
+            lv == rv && same(lt, rt, seen)
+        }
+        (Term::BooleanLiteral { typ: lt, value: lv, .. },
+            Term::BooleanLiteral { typ: rt, value: rv, .. }) => {
+            lv == rv && same(lt, rt, seen)
+        }
+        (Term::Variable { typ: lt, name: ln, guard: lg, .. },
+            Term::Variable { typ: rt, name: rn, guard: rg, .. }) => {
+            ln == rn && same(lt, rt, seen) && same(lg, rg, seen)
+        }
+        (Term::StaticMap { domain: ld, codomain: lc, .. },
+            Term::StaticMap { domain: rd, codomain: rc, .. }) => {
+            same(ld, rd, seen) && same(lc, rc, seen)
+        }
+        (Term::StaticProduct { lhs: ll, rhs: lr, .. },
+            Term::StaticProduct { lhs: rl, rhs: rr, .. }) => {
+            same(ll, rl, seen) && same(lr, rr, seen)
+        }
+        (Term::Lambda { param: lp, body: lb, guard: lg, .. },
+            Term::Lambda { param: rp, body: rb, guard: rg, .. }) => {
+            same(lp, rp, seen) && same(lb, rb, seen) && same(lg, rg, seen)
+        }
+        _ => false,
+    };
+    if result {
+        seen.insert(key);
+    }
+    result
+}
+
+/// Replace the variables in a term that have values.  Variables without a value are left alone.
+pub fn bind(fact: &TermFactory, term: &Arc<Term>, bindings: &[(String, Arc<Term>)]) -> Arc<Term> {
+    substitute(fact, term, bindings, &mut HashMap::new())
+}
+
+/// Replace the bound variables in a term.  Shared subterms stay shared.
+fn substitute(fact: &TermFactory, term: &Arc<Term>, bindings: &[(String, Arc<Term>)],
+    done: &mut HashMap<*const Term, Arc<Term>>) -> Arc<Term> {
+    let key = &**term as *const Term;
+    if let Some(result) = done.get(&key) {
+        return result.clone();
+    }
+    let result = match **term {
+        Term::Variable { ref name, .. } => {
+            match bindings.iter().find(|(bound, _)| bound == name) {
+                Some((_, value)) => value.clone(),
+                None => term.clone(),
+            }
+        }
+        Term::StaticMap { ref locus, ref domain, ref codomain } => {
+            let domain = substitute(fact, domain, bindings, done);
+            let codomain = substitute(fact, codomain, bindings, done);
+            fact.new_static_map(locus.clone(), &domain, &codomain)
+        }
+        Term::StaticProduct { ref locus, ref lhs, ref rhs } => {
+            let lhs = substitute(fact, lhs, bindings, done);
+            let rhs = substitute(fact, rhs, bindings, done);
+            fact.new_static_product(locus.clone(), &lhs, &rhs)
+        }
+        Term::Lambda { ref locus, ref param, ref body, ref guard } => {
+            let param = substitute(fact, param, bindings, done);
+            let body = substitute(fact, body, bindings, done);
+            let guard = substitute(fact, guard, bindings, done);
+            fact.new_lambda(locus.clone(), &param, &body, &guard)
+        }
+        _ => term.clone(),
+    };
+    done.insert(key, result.clone());
+    result
+}
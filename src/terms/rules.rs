@@ -0,0 +1,129 @@
+//! Define rewrite rules and keep track of them.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use super::terms::*;
+use terms::locus::Locus;
+
+/// The name of the ruleset that rules are placed in when they do not name one.  This ruleset
+/// is enabled when a rule library is created.
+pub const DEFAULT_RULESET: &str = "DEFAULT";
+
+/// A rewrite rule.  A term that matches the pattern, and for which the guard is true once the
+/// pattern variables are bound, is replaced by the rewrite.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    /// Where the rule was declared.
+    pub locus: Locus,
+    /// The pattern to match.
+    pub pattern: Arc<Term>,
+    /// The replacement.
+    pub rewrite: Arc<Term>,
+    /// The guard that must be true for the rule to apply.
+    pub guard: Arc<Term>,
+    /// The rulesets this rule belongs to.  This is never empty.
+    pub rulesets: Vec<String>,
+}
+
+/// The rule library holds every rule that has been declared, along with the set of enabled
+/// rulesets.  Rules are kept in declaration order, which is the order they are tried.
+#[derive(Clone, Debug)]
+pub struct RuleLibrary {
+    /// The rules, in declaration order.
+    rules: Vec<Rule>,
+    /// Every ruleset that has been mentioned, either by a rule or by enabling it.
+    known: BTreeSet<String>,
+    /// The rulesets that are enabled.
+    enabled: BTreeSet<String>,
+}
+
+impl Default for RuleLibrary {
+    fn default() -> Self {
+        let mut enabled = BTreeSet::new();
+        enabled.insert(DEFAULT_RULESET.to_string());
+        RuleLibrary {
+            rules: vec![],
+            known: enabled.clone(),
+            enabled,
+        }
+    }
+}
+
+impl RuleLibrary {
+    /// Make a new rule library with no rules and only the default ruleset enabled.
+    pub fn new() -> Self {
+        RuleLibrary::default()
+    }
+
+    /// Add a rule.  If the rule does not name any rulesets, it is placed in the default
+    /// ruleset.
+    pub fn add(&mut self, mut rule: Rule) {
+        if rule.rulesets.is_empty() {
+            rule.rulesets.push(DEFAULT_RULESET.to_string());
+        }
+        for name in &rule.rulesets {
+            self.known.insert(name.clone());
+        }
+        self.rules.push(rule);
+    }
+
+    /// Enable a ruleset.  The ruleset need not have any rules yet.
+    pub fn enable(&mut self, ruleset: &str) {
+        self.known.insert(ruleset.to_string());
+        self.enabled.insert(ruleset.to_string());
+    }
+
+    /// Disable a ruleset.
+    pub fn disable(&mut self, ruleset: &str) {
+        self.enabled.remove(ruleset);
+    }
+
+    /// Determine if a ruleset is enabled.
+    pub fn is_enabled(&self, ruleset: &str) -> bool {
+        self.enabled.contains(ruleset)
+    }
+
+    /// Iterate over the names of all known rulesets, in sorted order.
+    pub fn rulesets(&self) -> ::std::collections::btree_set::Iter<'_, String> {
+        self.known.iter()
+    }
+
+    /// Get the number of rules.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Determine if there are no rules.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Iterate over all rules in declaration order, enabled or not.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, Rule> {
+        self.rules.iter()
+    }
+
+    /// Get the rules that belong to at least one enabled ruleset, in declaration order.
+    pub fn enabled_rules(&self) -> Vec<&Rule> {
+        self.rules.iter()
+            .filter(|rule| rule.rulesets.iter().any(|name| self.enabled.contains(name)))
+            .collect()
+    }
+}
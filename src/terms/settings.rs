@@ -0,0 +1,120 @@
+//! Hold the settings that control a universe.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+use std::collections::BTreeMap;
+use error::{Error, Result};
+
+/// The kind of value a setting holds.  Values are always stored as text, but they are checked
+/// against the kind when they are set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SettingKind {
+    /// Either `true` or `false`.
+    Boolean,
+    /// A non-negative integer.
+    Count,
+    /// Any text.
+    Text,
+}
+
+/// A single setting.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Setting {
+    /// The kind of value the setting holds.
+    pub kind: SettingKind,
+    /// The current value.
+    pub value: String,
+    /// A short description of the setting.
+    pub description: &'static str,
+}
+
+/// The settings of a universe.  Only settings that are known can be changed, and values are
+/// checked against the kind of the setting.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    /// The settings, by name.
+    values: BTreeMap<String, Setting>,
+}
+
+/// The known settings, their kinds, defaults, and descriptions.
+const DEFAULTS: &[(&str, SettingKind, &str, &str)] = &[
+    ("rewrite_limit", SettingKind::Count, "10000",
+        "The most rewrites performed on a single term."),
+];
+
+impl Default for Settings {
+    fn default() -> Self {
+        let mut values = BTreeMap::new();
+        for &(name, kind, value, description) in DEFAULTS {
+            values.insert(name.to_string(), Setting {
+                kind,
+                value: value.to_string(),
+                description,
+            });
+        }
+        Settings { values }
+    }
+}
+
+impl Settings {
+    /// Make the default settings.
+    pub fn new() -> Self {
+        Settings::default()
+    }
+
+    /// Get a setting by name.
+    pub fn get(&self, name: &str) -> Option<&Setting> {
+        self.values.get(name)
+    }
+
+    /// Change a setting.  The setting must be known, and the value must be of the right kind.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        let setting = match self.values.get_mut(name) {
+            Some(setting) => setting,
+            None => return Err(Error::Config(format!("There is no setting named {}.", name))),
+        };
+        let valid = match setting.kind {
+            SettingKind::Boolean => value == "true" || value == "false",
+            SettingKind::Count => value.parse::<u64>().is_ok(),
+            SettingKind::Text => true,
+        };
+        if !valid {
+            return Err(Error::Config(format!("The value {} is not valid for the setting {}.",
+                value, name)));
+        }
+        setting.value = value.to_string();
+        Ok(())
+    }
+
+    /// Get the value of a Boolean setting.  Unknown settings and settings of another kind
+    /// are false.
+    pub fn get_bool(&self, name: &str) -> bool {
+        self.get(name).map(|setting| setting.value == "true").unwrap_or(false)
+    }
+
+    /// Get the value of a count setting.  Unknown settings and settings of another kind
+    /// are zero.
+    pub fn get_count(&self, name: &str) -> u64 {
+        self.get(name).and_then(|setting| setting.value.parse().ok()).unwrap_or(0)
+    }
+
+    /// Iterate over the settings in order by name.
+    pub fn iter(&self) -> ::std::collections::btree_map::Iter<'_, String, Setting> {
+        self.values.iter()
+    }
+}
@@ -0,0 +1,473 @@
+//! Write and read terms as S-expressions.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+The S-expression form is explicit: when every type is written, two terms are equal (ignoring
+loci) exactly when they are written the same way.
+
+    ^ROOT                           The root term.
+    NAME                            A named root term, such as ANY or STRING.
+    true, false                     The Boolean literals of type BOOLEAN.
+    (symbol NAME [TYPE])            A symbol literal.
+    (string "TEXT" [TYPE])          A string literal.
+    (boolean true [TYPE])           A Boolean literal.
+    (var NAME [TYPE] GUARD)         A variable.
+    (map DOMAIN CODOMAIN)           A static map.
+    (product LHS RHS)               A static product.
+    (lambda PARAM BODY GUARD)       A lambda.
+
+A missing type is the default type for that kind of term.  By default the writer writes every
+type, so the output is fully explicit, as in (lambda (var x ANY true) (symbol y SYMBOL) true).
+The short forms for named root terms and for Boolean literals of type BOOLEAN are still used,
+since they stand for exactly one term each.
+
+Names that contain anything other than ASCII letters, digits, and a few punctuation characters
+(see is_atom_char) are enclosed in vertical bars, as in |two words|.  Within vertical bars and
+strings, the delimiter and the backslash are escaped by a preceding backslash, and characters
+outside of ASCII can be written as \u{HHHH}.  Comments run from a semicolon to the end of the
+line, or from #| to |#.  Loci are written as block comments in front of terms if the writer is
+configured to write them, and are ignored when reading.
+*/
+
+use std::io;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::util::{TermWriter, WriterConfig, ShowTypes};
+use diagnostics::Diagnostic;
+use error::{Error, Result};
+use terms::locus::Locus;
+
+/// Write a term as an S-expression.  Unlike the other writers, every type is written unless
+/// the writer is configured otherwise.
+pub struct SexpWriter {
+    /// The options shared by all writers.
+    config: WriterConfig,
+}
+
+impl SexpWriter {
+    /// Write the type of a literal or variable, preceded by a space, if the configuration calls
+    /// for it.
+    fn typ(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        if self.config.shows_type(fact, term) {
+            write!(dest, " ")?;
+            self.write(dest, fact, &fact.get_type(term))?;
+        }
+        Ok(())
+    }
+}
+
+impl TermWriter for SexpWriter {
+    fn new() -> Self {
+        SexpWriter { config: WriterConfig { types: ShowTypes::Always, ..WriterConfig::new() } }
+    }
+
+    fn config(&self) -> &WriterConfig {
+        &self.config
+    }
+
+    fn set_config(&mut self, config: WriterConfig) {
+        self.config = config;
+    }
+
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()> {
+        if self.config.loci {
+            let locus = fact.get_locus(term);
+            if locus != Locus::Internal {
+                write!(dest, "#| {} |# ", locus)?;
+            }
+        }
+        let ascii = self.config.ascii;
+        match **term {
+            Term::Root => write!(dest, "^ROOT"),
+            Term::SymbolLiteral { ref typ, ref value, .. } => {
+                if **typ == Term::Root && fact.is_named_root_term(value) {
+                    return write!(dest, "{}", atom(value, ascii));
+                }
+                write!(dest, "(symbol {}", atom(value, ascii))?;
+                self.typ(dest, fact, term)?;
+                write!(dest, ")")
+            }
+            Term::StringLiteral { ref value, .. } => {
+                write!(dest, "(string {}", delimit(value, '"', ascii))?;
+                self.typ(dest, fact, term)?;
+                write!(dest, ")")
+            }
+            Term::BooleanLiteral { ref typ, value, .. } => {
+                if !self.config.shows_type(fact, term) || *typ == fact.get_boolean() {
+                    return write!(dest, "{}", value);
+                }
+                write!(dest, "(boolean {}", value)?;
+                self.typ(dest, fact, term)?;
+                write!(dest, ")")
+            }
+            Term::Variable { ref name, ref guard, .. } => {
+                write!(dest, "(var {}", atom(name, ascii))?;
+                self.typ(dest, fact, term)?;
+                write!(dest, " ")?;
+                self.write(dest, fact, guard)?;
+                write!(dest, ")")
+            }
+            Term::StaticMap { ref domain, ref codomain, .. } => {
+                write!(dest, "(map ")?;
+                self.write(dest, fact, domain)?;
+                write!(dest, " ")?;
+                self.write(dest, fact, codomain)?;
+                write!(dest, ")")
+            }
+            Term::StaticProduct { ref lhs, ref rhs, .. } => {
+                write!(dest, "(product ")?;
+                self.write(dest, fact, lhs)?;
+                write!(dest, " ")?;
+                self.write(dest, fact, rhs)?;
+                write!(dest, ")")
+            }
+            Term::Lambda { ref param, ref body, ref guard, .. } => {
+                write!(dest, "(lambda ")?;
+                self.write(dest, fact, param)?;
+                write!(dest, " ")?;
+                self.write(dest, fact, body)?;
+                write!(dest, " ")?;
+                self.write(dest, fact, guard)?;
+                write!(dest, ")")
+            }
+        }
+    }
+}
+
+/// Determine if a character may appear in a bare atom.
+fn is_atom_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || "-_+*/<>=!?.".contains(ch)
+}
+
+/// Write a name as an atom, adding vertical bars if needed.
+fn atom(name: &str, ascii: bool) -> String {
+    let reserved = name == "true" || name == "false";
+    if !name.is_empty() && !reserved && name.chars().all(is_atom_char) {
+        name.to_string()
+    } else {
+        delimit(name, '|', ascii)
+    }
+}
+
+/// Enclose text in a delimiter, escaping the delimiter and backslash, and escaping characters
+/// outside of ASCII if `ascii` is set.
+fn delimit(text: &str, border: char, ascii: bool) -> String {
+    let mut out = border.to_string();
+    for ch in text.chars() {
+        if ascii && !ch.is_ascii() {
+            out.push_str(&format!("\\u{{{:04X}}}", ch as u32));
+            continue;
+        }
+        if ch == border || ch == '\\' {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out.push(border);
+    out
+}
+
+/// An S-expression.
+#[derive(Debug)]
+enum Sexp {
+    /// An atom, with a flag that is set if the atom was enclosed in vertical bars.
+    Atom(String, bool, Locus),
+    /// A string.
+    Str(String, Locus),
+    /// A list.
+    List(Vec<Sexp>, Locus),
+}
+
+impl Sexp {
+    /// Get where this expression starts.
+    fn locus(&self) -> &Locus {
+        match *self {
+            Sexp::Atom(_, _, ref locus) | Sexp::Str(_, ref locus) | Sexp::List(_, ref locus) => {
+                locus
+            }
+        }
+    }
+}
+
+/// Read terms written by an `SexpWriter`.
+pub struct SexpReader<'a> {
+    /// The factory used to make terms.
+    fact: &'a TermFactory,
+}
+
+impl<'a> SexpReader<'a> {
+    /// Read a term written by an `SexpWriter`.  The whole source is read.
+    pub fn read(src: &mut dyn io::Read, fact: &TermFactory) -> Result<Arc<Term>> {
+        let mut text = String::new();
+        src.read_to_string(&mut text)?;
+        SexpReader::read_str(&text, fact)
+    }
+
+    /// Read a term from S-expression text.
+    pub fn read_str(text: &str, fact: &TermFactory) -> Result<Arc<Term>> {
+        let mut lexer = SexpParser { chars: text.chars().collect(), pos: 0, line: 1, column: 1 };
+        lexer.skip();
+        let sexp = lexer.sexp()?;
+        lexer.skip();
+        if lexer.pos != lexer.chars.len() {
+            return Err(error(&lexer.locus(), "unexpected text after the term"));
+        }
+        SexpReader { fact }.term(&sexp)
+    }
+
+    /// Convert an S-expression to a term.
+    fn term(&self, sexp: &Sexp) -> Result<Arc<Term>> {
+        let fact = self.fact;
+        let items = match *sexp {
+            Sexp::Atom(ref name, false, _) if name == "^ROOT" => return Ok(fact.get_root()),
+            Sexp::Atom(ref name, false, _) if name == "true" => return Ok(fact.new_boolean(true)),
+            Sexp::Atom(ref name, false, _) if name == "false" => {
+                return Ok(fact.new_boolean(false));
+            }
+            Sexp::Atom(ref name, _, ref locus) => {
+                return match fact.get_named_root_term(name) {
+                    Some(term) => Ok(term),
+                    None => Err(error(locus, &format!("{} is not a named root term", name))),
+                };
+            }
+            Sexp::Str(_, ref locus) => return Err(error(locus, "expected a term, not a string")),
+            Sexp::List(ref items, _) => items,
+        };
+        let head = match items.first() {
+            Some(&Sexp::Atom(ref head, false, _)) => head.as_str(),
+            _ => return Err(error(sexp.locus(), "expected a list starting with a kind of term")),
+        };
+        // Literals and variables may leave out their type.
+        let (arity, typed) = match head {
+            "symbol" | "string" | "boolean" => (2, true),
+            "var" => (3, true),
+            "map" | "product" => (2, false),
+            "lambda" => (3, false),
+            _ => return Err(error(sexp.locus(), &format!("unknown kind of term {}", head))),
+        };
+        if items.len() != arity + 1 && !(typed && items.len() == arity) {
+            return Err(error(sexp.locus(), &format!("{} takes {} arguments", head, arity)));
+        }
+        let typ = if items.len() == arity + 1 && typed {
+            Some(self.term(&items[2])?)
+        } else {
+            None
+        };
+        match head {
+            "symbol" => {
+                let name = self.name(&items[1])?;
+                let typ = typ.unwrap_or_else(|| if fact.is_named_root_term(&name) {
+                    fact.get_root()
+                } else {
+                    fact.get_symbol()
+                });
+                Ok(fact.new_typed_symbol(Locus::Internal, name, typ))
+            }
+            "string" => {
+                let value = match items[1] {
+                    Sexp::Str(ref value, _) => value.clone(),
+                    ref other => return Err(error(other.locus(), "expected a string")),
+                };
+                let typ = typ.unwrap_or_else(|| fact.get_string());
+                Ok(fact.new_typed_string(Locus::Internal, value, typ))
+            }
+            "boolean" => {
+                let value = match items[1] {
+                    Sexp::Atom(ref value, false, _) if value == "true" => true,
+                    Sexp::Atom(ref value, false, _) if value == "false" => false,
+                    ref other => return Err(error(other.locus(), "expected true or false")),
+                };
+                let typ = typ.unwrap_or_else(|| fact.get_boolean());
+                Ok(fact.new_typed_boolean(Locus::Internal, value, typ))
+            }
+            "var" => {
+                let name = self.name(&items[1])?;
+                let typ = typ.unwrap_or_else(|| fact.get_any());
+                let guard = self.term(items.last().unwrap())?;
+                Ok(fact.new_variable(Locus::Internal, &typ, name, &guard))
+            }
+            "map" => {
+                let domain = self.term(&items[1])?;
+                let codomain = self.term(&items[2])?;
+                Ok(fact.new_static_map(Locus::Internal, &domain, &codomain))
+            }
+            "product" => {
+                let lhs = self.term(&items[1])?;
+                let rhs = self.term(&items[2])?;
+                Ok(fact.new_static_product(Locus::Internal, &lhs, &rhs))
+            }
+            _ => {
+                let param = self.term(&items[1])?;
+                let body = self.term(&items[2])?;
+                let guard = self.term(&items[3])?;
+                Ok(fact.new_lambda(Locus::Internal, &param, &body, &guard))
+            }
+        }
+    }
+
+    /// Read a name, which must be an atom.
+    fn name(&self, sexp: &Sexp) -> Result<String> {
+        match *sexp {
+            Sexp::Atom(ref name, _, _) => Ok(name.clone()),
+            ref other => Err(error(other.locus(), "expected a name")),
+        }
+    }
+}
+
+/// Make an error at a locus.
+fn error(locus: &Locus, message: &str) -> Error {
+    Error::Parse(Box::new(Diagnostic::error(message).with_primary(locus.clone(), 1, "")))
+}
+
+/// Read S-expressions from text.
+struct SexpParser {
+    chars: Vec<char>,
+    pos: usize,
+    line: u32,
+    column: u32,
+}
+
+impl SexpParser {
+    /// Get the locus of the next character.
+    fn locus(&self) -> Locus {
+        Locus::Console(self.line, self.column)
+    }
+
+    /// Consume a character.
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.chars.get(self.pos).cloned();
+        if let Some(ch) = ch {
+            self.pos += 1;
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        ch
+    }
+
+    /// Skip white space and comments.  A comment runs from a semicolon to the end of the line,
+    /// or from `#|` to `|#`.
+    fn skip(&mut self) {
+        while let Some(&ch) = self.chars.get(self.pos) {
+            if ch == ';' {
+                while let Some(ch) = self.bump() {
+                    if ch == '\n' {
+                        break;
+                    }
+                }
+            } else if ch == '#' && self.chars.get(self.pos + 1) == Some(&'|') {
+                self.bump();
+                self.bump();
+                while self.pos < self.chars.len() {
+                    if self.bump() == Some('|') && self.chars.get(self.pos) == Some(&'#') {
+                        self.bump();
+                        break;
+                    }
+                }
+            } else if ch.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// Read one S-expression.  White space before it has been skipped.
+    fn sexp(&mut self) -> Result<Sexp> {
+        let locus = self.locus();
+        match self.chars.get(self.pos).cloned() {
+            None => Err(error(&locus, "expected a term but found the end of the input")),
+            Some('(') => {
+                self.bump();
+                let mut items = vec![];
+                loop {
+                    self.skip();
+                    match self.chars.get(self.pos) {
+                        Some(&')') => {
+                            self.bump();
+                            return Ok(Sexp::List(items, locus));
+                        }
+                        None => return Err(error(&locus, "missing closing parenthesis")),
+                        _ => items.push(self.sexp()?),
+                    }
+                }
+            }
+            Some(')') => Err(error(&locus, "unexpected closing parenthesis")),
+            Some('"') => Ok(Sexp::Str(self.delimited('"')?, locus)),
+            Some('|') => Ok(Sexp::Atom(self.delimited('|')?, true, locus)),
+            Some(_) => {
+                let mut name = String::new();
+                while let Some(&ch) = self.chars.get(self.pos) {
+                    if ch.is_whitespace() || ch == '(' || ch == ')' || ch == '"' || ch == '|' ||
+                        ch == ';' || (ch == '#' && self.chars.get(self.pos + 1) == Some(&'|')) {
+                        break;
+                    }
+                    name.push(ch);
+                    self.bump();
+                }
+                Ok(Sexp::Atom(name, false, locus))
+            }
+        }
+    }
+
+    /// Read text enclosed in a delimiter.
+    fn delimited(&mut self, border: char) -> Result<String> {
+        let locus = self.locus();
+        self.bump();
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                None => {
+                    return Err(error(&locus, &format!("missing closing {}", border)));
+                }
+                Some('\\') => match self.bump() {
+                    Some(ch) if ch == border || ch == '\\' => text.push(ch),
+                    Some('u') => text.push(self.unicode(&locus)?),
+                    _ => return Err(error(&locus, "invalid escape")),
+                },
+                Some(ch) if ch == border => return Ok(text),
+                Some(ch) => text.push(ch),
+            }
+        }
+    }
+
+    /// Read the rest of a `\u{HHHH}` escape, after the `u`.
+    fn unicode(&mut self, locus: &Locus) -> Result<char> {
+        if self.bump() != Some('{') {
+            return Err(error(locus, "invalid escape"));
+        }
+        let mut digits = String::new();
+        loop {
+            match self.bump() {
+                Some('}') => break,
+                Some(ch) if ch.is_ascii_hexdigit() && digits.len() < 6 => digits.push(ch),
+                _ => return Err(error(locus, "invalid escape")),
+            }
+        }
+        u32::from_str_radix(&digits, 16).ok()
+            .and_then(::std::char::from_u32)
+            .ok_or_else(|| error(locus, "invalid escape"))
+    }
+}
@@ -81,7 +81,11 @@ pub struct TermFactory {
      */
 
     /// Map names to root terms.
-    named_terms: HashMap<String, Arc<Term>>
+    named_terms: HashMap<String, Arc<Term>>,
+
+    /// The names of root terms that were declared after the factory was made, in the order
+    /// they were declared.
+    declared_roots: Vec<String>,
 }
 
 /// Local macro to create a new root term.
@@ -156,7 +160,8 @@ impl TermFactory {
             the_properties: properties,
 
             // Save the map.
-            named_terms: hmap
+            named_terms: hmap,
+            declared_roots: vec![],
         };
 
         // The factory is done.
@@ -177,6 +182,28 @@ impl TermFactory {
         self.named_terms.contains_key(name)
     }
 
+    /// Declare a new named root term, such as a new type.  If a root term with the name
+    /// already exists, it is returned and nothing is declared.
+    pub fn declare_root(&mut self, name: &str) -> Arc<Term> {
+        if let Some(term) = self.named_terms.get(name) {
+            return term.clone();
+        }
+        let term = nrt!(self.the_root, name.to_string());
+        self.named_terms.insert(name.to_string(), term.clone());
+        self.declared_roots.push(name.to_string());
+        term
+    }
+
+    /// Get the names of the root terms declared with `declare_root`, in declaration order.
+    pub fn declared_roots(&self) -> &[String] {
+        &self.declared_roots
+    }
+
+    /// Iterate over the names of all named root terms, in no particular order.
+    pub fn named_terms(&self) -> ::std::collections::hash_map::Keys<'_, String, Arc<Term>> {
+        self.named_terms.keys()
+    }
+
     /// Get the unique root term.
     pub fn get_root(&self) -> Arc<Term> {
         self.the_root.clone()
@@ -20,3 +20,261 @@
 // A term universe contains everything that is needed to operate with terms.
 // This includes the terms, factories, and methods for writing and reading
 // them.
+//
+// Nothing in a universe is global, so any number of independent universes can
+// exist in one process.  Terms made by one universe should not be given to
+// another, since the well-known root terms are not shared between factories.
+
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use super::terms::*;
+use super::termfactory::TermFactory;
+use super::operators::OperatorLibrary;
+use super::rules::{RuleLibrary, DEFAULT_RULESET};
+use super::rewriter::Rewriter;
+use super::checker;
+use super::settings::Settings;
+use super::util::{TermWriter, WriterConfig, ShowTypes, Parens, Display};
+use super::eli_writer::{EliWriter, quote_name};
+use super::eli_parser::{EliParser, Statement};
+use diagnostics::Diagnostic;
+use error::{Error, Result};
+use terms::locus::Locus;
+use util::escape;
+
+/// A term universe.  This is the single handle that owns the term factory, the declared
+/// operators and rules, the settings, and the writer used to show terms.
+pub struct Universe {
+    /// The factory used to make terms.
+    factory: TermFactory,
+    /// The declared operators.
+    operators: OperatorLibrary,
+    /// The declared rules.
+    rules: RuleLibrary,
+    /// The settings.
+    settings: Settings,
+    /// The writer used to show terms.
+    writer: Box<dyn TermWriter>,
+}
+
+impl Default for Universe {
+    fn default() -> Self {
+        Universe::new()
+    }
+}
+
+impl Universe {
+    /// Make a new universe with no operators or rules, the default settings, and an ELI
+    /// writer.
+    pub fn new() -> Self {
+        Universe {
+            factory: TermFactory::new(),
+            operators: OperatorLibrary::new(),
+            rules: RuleLibrary::new(),
+            settings: Settings::new(),
+            writer: Box::new(EliWriter::new()),
+        }
+    }
+
+    /// Get the term factory.
+    pub fn factory(&self) -> &TermFactory {
+        &self.factory
+    }
+
+    /// Get the term factory so that new root terms can be declared.
+    pub fn factory_mut(&mut self) -> &mut TermFactory {
+        &mut self.factory
+    }
+
+    /// Get the declared operators.
+    pub fn operators(&self) -> &OperatorLibrary {
+        &self.operators
+    }
+
+    /// Get the declared operators so they can be changed.
+    pub fn operators_mut(&mut self) -> &mut OperatorLibrary {
+        &mut self.operators
+    }
+
+    /// Get the declared rules.
+    pub fn rules(&self) -> &RuleLibrary {
+        &self.rules
+    }
+
+    /// Get the declared rules so they can be changed.
+    pub fn rules_mut(&mut self) -> &mut RuleLibrary {
+        &mut self.rules
+    }
+
+    /// Get the settings.
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Get the settings so they can be changed.
+    pub fn settings_mut(&mut self) -> &mut Settings {
+        &mut self.settings
+    }
+
+    /// Get the writer used to show terms.
+    pub fn writer(&self) -> &dyn TermWriter {
+        &*self.writer
+    }
+
+    /// Get the writer used to show terms so it can be configured.
+    pub fn writer_mut(&mut self) -> &mut dyn TermWriter {
+        &mut *self.writer
+    }
+
+    /// Replace the writer used to show terms.
+    pub fn set_writer(&mut self, writer: Box<dyn TermWriter>) {
+        self.writer = writer;
+    }
+
+    /// Write a term using this universe's writer.
+    pub fn write(&self, dest: &mut dyn io::Write, term: &Arc<Term>) -> io::Result<()> {
+        self.writer.write(dest, &self.factory, term)
+    }
+
+    /// Get an adapter that shows a term with this universe's writer when it is formatted.
+    pub fn display<'a>(&'a self, term: &'a Arc<Term>) -> Display<'a> {
+        Display::new(&*self.writer, &self.factory, term)
+    }
+
+    /// Print a term to standard out using this universe's writer, followed by a newline.
+    pub fn println(&self, term: &Arc<Term>) -> Result<()> {
+        self.writer.println(&self.factory, term)
+    }
+
+    /// Rewrite a term with the enabled rules until no rule applies.  At most `rewrite_limit`
+    /// rewrites are performed; if more are needed, an `Error::RewriteLimit` is returned.  Guards
+    /// that need themselves without end are an `Error::GuardDepth`.
+    pub fn rewrite(&self, term: &Arc<Term>) -> Result<Arc<Term>> {
+        let limit = self.settings.get_count("rewrite_limit");
+        Rewriter::new(&self.factory, &self.rules, limit).rewrite(term)
+    }
+
+    /// Perform a declaration.  Terms are not declarations, so they are returned for the caller
+    /// to deal with, and `None` is returned for everything else.  Loading files is left to a
+    /// `Loader`, so a load statement is an error here.  Terms, operator types, and rules are
+    /// checked first, and a type that is not a declared root is an `Error::Type`.
+    pub fn execute(&mut self, statement: Statement) -> Result<Option<Arc<Term>>> {
+        match statement {
+            Statement::Term(term) => {
+                checker::check(&self.factory, &term)?;
+                return Ok(Some(term));
+            }
+            Statement::Root(_, name) => {
+                self.factory.declare_root(&name);
+            }
+            Statement::Operator(operator) => {
+                checker::check_type(&self.factory, &operator.typ)?;
+                self.operators.declare(operator);
+            }
+            Statement::Rule(rule) => {
+                for part in &[&rule.pattern, &rule.rewrite, &rule.guard] {
+                    checker::check(&self.factory, part)?;
+                }
+                self.rules.add(rule);
+            }
+            Statement::Enable(_, name) => self.rules.enable(&name),
+            Statement::Disable(_, name) => self.rules.disable(&name),
+            Statement::Set(_, name, value) => self.settings.set(&name, &value)?,
+            Statement::Load(locus, name, width) => {
+                let message = format!("the file {} cannot be loaded here", name);
+                return Err(Error::Parse(Box::new(Diagnostic::error(&message)
+                    .with_primary(locus, width, "")
+                    .with_note("files are loaded by a loader, not by the universe"))));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Save this universe as a context file.  The file holds the declared root terms,
+    /// operators, rules, ruleset enablement, and settings, and can be read back with `load`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = File::create(path)?;
+        self.save_to(&mut file)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Write this universe as a context to the given destination.  See `save`.  The writer is
+    /// configured here, rather than taken from the defaults, so that every term is written with
+    /// the parentheses and types it needs to be read back the same, one statement to a line.
+    pub fn save_to(&self, dest: &mut dyn io::Write) -> Result<()> {
+        let mut eli = EliWriter::new().with_width(usize::MAX);
+        eli.set_config(WriterConfig {
+            types: ShowTypes::WhenNeeded,
+            loci: false,
+            parens: Parens::Minimal,
+            ascii: true,
+            sharing: false,
+        });
+        let fact = &self.factory;
+        writeln!(dest, "// relision context")?;
+        for name in fact.declared_roots() {
+            writeln!(dest, "root {};", quote_name(name, true))?;
+        }
+        for operator in self.operators.iter() {
+            write!(dest, "operator {}: ", quote_name(&operator.name, true))?;
+            eli.write(dest, fact, &operator.typ)?;
+            if !operator.description.is_empty() {
+                let (escaped, _) = escape(&operator.description, '"');
+                write!(dest, " \"{}\"", escaped)?;
+            }
+            writeln!(dest, ";")?;
+        }
+        for rule in self.rules.iter() {
+            let lambda = fact.new_lambda(Locus::Internal, &rule.pattern, &rule.rewrite,
+                &rule.guard);
+            write!(dest, "rule ")?;
+            eli.write(dest, fact, &lambda)?;
+            let names: Vec<String> =
+                rule.rulesets.iter().map(|name| quote_name(name, true)).collect();
+            writeln!(dest, " in {};", names.join(", "))?;
+        }
+        for name in self.rules.rulesets() {
+            if self.rules.is_enabled(name) {
+                if name != DEFAULT_RULESET {
+                    writeln!(dest, "enable {};", quote_name(name, true))?;
+                }
+            } else {
+                writeln!(dest, "disable {};", quote_name(name, true))?;
+            }
+        }
+        for (name, setting) in self.settings.iter() {
+            let (escaped, _) = escape(&setting.value, '"');
+            writeln!(dest, "set {} = \"{}\";", quote_name(name, true), escaped)?;
+        }
+        Ok(())
+    }
+
+    /// Make a new universe from a context file written by `save`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Universe> {
+        let mut text = String::new();
+        File::open(&path)?.read_to_string(&mut text)?;
+        let name = path.as_ref().to_string_lossy().into_owned();
+        let mut universe = Universe::new();
+        universe.load_from(&text, Locus::File(name, 1, 1))?;
+        Ok(universe)
+    }
+
+    /// Perform all the declarations in the given context text.  Terms are not allowed.
+    ///   * `text`: The text of the context.
+    ///   * `start`: Where the text starts.
+    pub fn load_from(&mut self, text: &str, start: Locus) -> Result<()> {
+        let mut parser = EliParser::new(text, start);
+        while let Some(statement) = parser.next_statement(&self.factory)? {
+            if let Some(term) = self.execute(statement)? {
+                return Err(Error::Parse(Box::new(
+                    Diagnostic::error("a context may only contain declarations")
+                        .with_primary(self.factory.get_locus(&term), 1, "this is a term"))));
+            }
+        }
+        Ok(())
+    }
+}
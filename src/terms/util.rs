@@ -17,22 +17,259 @@
 //! that is part of this distribution.  This file may not be copied,
 //! modified, or distributed except according to those terms.
 
+use std::fmt;
 use std::io;
+use std::io::Write;
 use std::sync::Arc;
 use super::terms::*;
 use super::termfactory::TermFactory;
+use error::{Error, Result};
+
+/// When to write the type of a term.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ShowTypes {
+    /// Write every type.
+    Always,
+    /// Write no types.  Readers assume the default type.
+    Never,
+    /// Write a type only when it is not the default for that kind of term.
+    WhenNeeded,
+}
+
+/// How many parentheses to write.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parens {
+    /// Enclose every map, product, and lambda that is part of a larger term.
+    Full,
+    /// Write only the parentheses needed to read the term back.
+    Minimal,
+}
+
+/// The options every writer honours.  Writers for formats that have no use for an option, or
+/// cannot express it, ignore it.  For example, the S-expression form is always fully
+/// parenthesized, and only the ELI form can write bindings for shared subterms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WriterConfig {
+    /// When to write types.
+    pub types: ShowTypes,
+    /// Whether to write loci as comments.
+    pub loci: bool,
+    /// How many parentheses to write.
+    pub parens: Parens,
+    /// Whether to escape every character outside of ASCII, or only control characters.
+    pub ascii: bool,
+    /// Whether to write shared subterms once and refer to them by name.
+    pub sharing: bool,
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        WriterConfig {
+            types: ShowTypes::WhenNeeded,
+            loci: false,
+            parens: Parens::Minimal,
+            ascii: true,
+            sharing: false,
+        }
+    }
+}
+
+impl WriterConfig {
+    /// Make the default configuration.
+    pub fn new() -> Self {
+        WriterConfig::default()
+    }
+
+    /// Change an option by name.  The options are `types` (`always`, `never`, or `needed`),
+    /// `loci` (`true` or `false`), `parens` (`full` or `minimal`), `escapes` (`ascii` or
+    /// `unicode`), and `sharing` (`true` or `false`).
+    pub fn set(&mut self, name: &str, value: &str) -> Result<()> {
+        match (name, value) {
+            ("types", "always") => self.types = ShowTypes::Always,
+            ("types", "never") => self.types = ShowTypes::Never,
+            ("types", "needed") => self.types = ShowTypes::WhenNeeded,
+            ("loci", "true") => self.loci = true,
+            ("loci", "false") => self.loci = false,
+            ("parens", "full") => self.parens = Parens::Full,
+            ("parens", "minimal") => self.parens = Parens::Minimal,
+            ("escapes", "ascii") => self.ascii = true,
+            ("escapes", "unicode") => self.ascii = false,
+            ("sharing", "true") => self.sharing = true,
+            ("sharing", "false") => self.sharing = false,
+            ("types", _) | ("loci", _) | ("parens", _) | ("escapes", _) | ("sharing", _) => {
+                let choices = self.options().into_iter()
+                    .find(|&(option, _, _)| option == name)
+                    .map_or("", |(_, _, choices)| choices);
+                return Err(Error::Config(format!(
+                    "The value {} is not valid for the writer option {}; use one of {}.",
+                    value, name, choices)));
+            }
+            _ => return Err(Error::Config(format!("There is no writer option named {}.", name))),
+        }
+        Ok(())
+    }
+
+    /// Get the name, current value, and possible values of every option.
+    pub fn options(&self) -> Vec<(&'static str, &'static str, &'static str)> {
+        vec![
+            ("types", match self.types {
+                ShowTypes::Always => "always",
+                ShowTypes::Never => "never",
+                ShowTypes::WhenNeeded => "needed",
+            }, "always, never, needed"),
+            ("loci", if self.loci { "true" } else { "false" }, "true, false"),
+            ("parens", match self.parens {
+                Parens::Full => "full",
+                Parens::Minimal => "minimal",
+            }, "full, minimal"),
+            ("escapes", if self.ascii { "ascii" } else { "unicode" }, "ascii, unicode"),
+            ("sharing", if self.sharing { "true" } else { "false" }, "true, false"),
+        ]
+    }
+
+    /// Determine whether the type of a literal or variable should be written.  Terms that have
+    /// no type of their own, such as maps, are never given one.
+    pub fn shows_type(&self, fact: &TermFactory, term: &Term) -> bool {
+        match self.types {
+            ShowTypes::Always => default_type(fact, term).is_some(),
+            ShowTypes::Never => false,
+            ShowTypes::WhenNeeded => match (term_type(term), default_type(fact, term)) {
+                (Some(typ), Some(default)) => *typ != default,
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Get the type assumed for a literal or variable when none is given.  A symbol that names a
+/// root term has type `^ROOT`.
+pub fn default_type(fact: &TermFactory, term: &Term) -> Option<Arc<Term>> {
+    match *term {
+        Term::SymbolLiteral { ref value, .. } => {
+            if fact.is_named_root_term(value) {
+                Some(fact.get_root())
+            } else {
+                Some(fact.get_symbol())
+            }
+        }
+        Term::StringLiteral { .. } => Some(fact.get_string()),
+        Term::BooleanLiteral { .. } => Some(fact.get_boolean()),
+        Term::Variable { .. } => Some(fact.get_any()),
+        _ => None,
+    }
+}
+
+/// Get the type stored in a literal or variable.
+fn term_type(term: &Term) -> Option<&Arc<Term>> {
+    match *term {
+        Term::SymbolLiteral { ref typ, .. } |
+        Term::StringLiteral { ref typ, .. } |
+        Term::BooleanLiteral { ref typ, .. } |
+        Term::Variable { ref typ, .. } => Some(typ),
+        _ => None,
+    }
+}
+
+/// The precedence of lambdas, which bind most loosely.  The writers for every infix form use
+/// these, so that parentheses are written in the same places.
+pub const LAMBDA: u8 = 0;
+/// The precedence of static maps.
+pub const MAP: u8 = 1;
+/// The precedence of static products.
+pub const PRODUCT: u8 = 2;
+/// The precedence of everything else, including type annotations.
+pub const PRIMARY: u8 = 3;
+
+/// Get the precedence of a term.
+pub fn precedence(term: &Term) -> u8 {
+    match *term {
+        Term::Lambda { .. } => LAMBDA,
+        Term::StaticMap { .. } => MAP,
+        Term::StaticProduct { .. } => PRODUCT,
+        _ => PRIMARY,
+    }
+}
 
 /// Write a term.
 pub trait TermWriter {
     /// Make a new instance.
-    fn new() -> Self;
+    fn new() -> Self where Self: Sized;
 
-    /// Print directly to standard out.
-    fn print(&self, fact: &TermFactory, term: &Arc<Term>);
+    /// Get the configuration.
+    fn config(&self) -> &WriterConfig;
+
+    /// Replace the configuration.
+    fn set_config(&mut self, config: WriterConfig);
+
+    /// Replace the configuration, returning the writer.
+    fn with_config(mut self, config: WriterConfig) -> Self where Self: Sized {
+        self.set_config(config);
+        self
+    }
 
     /// Print directly to standard out.
-    fn println(&self, fact: &TermFactory, term: &Arc<Term>);
+    fn print(&self, fact: &TermFactory, term: &Arc<Term>) -> Result<()> {
+        self.write(&mut io::stdout(), fact, term)?;
+        Ok(())
+    }
+
+    /// Print directly to standard out, followed by a newline.
+    fn println(&self, fact: &TermFactory, term: &Arc<Term>) -> Result<()> {
+        let mut out = io::stdout();
+        self.write(&mut out, fact, term)?;
+        writeln!(out)?;
+        Ok(())
+    }
 
     /// Write a term to the given formatter.
-    fn write(&self, dest: &mut io::Write, fact: &TermFactory, term: &Arc<Term>) -> io::Result<()>;
+    fn write(&self, dest: &mut dyn io::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> io::Result<()>;
+
+    /// Write a term to a string.  Writing to memory does not fail, so an error can only come
+    /// from the writer itself.
+    fn to_string(&self, fact: &TermFactory, term: &Arc<Term>) -> Result<String> {
+        let mut out = vec![];
+        self.write(&mut out, fact, term)?;
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    /// Write a term to anything that implements `fmt::Write`, such as a `String` or a
+    /// `fmt::Formatter`.
+    fn write_fmt_to(&self, dest: &mut dyn fmt::Write, fact: &TermFactory, term: &Arc<Term>)
+        -> fmt::Result {
+        match self.to_string(fact, term) {
+            Ok(text) => dest.write_str(&text),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+
+    /// Get an adapter that implements `fmt::Display`, so a term can be used with `format!`.
+    /// For a trait object, use `Display::new`.
+    fn display<'a>(&'a self, fact: &'a TermFactory, term: &'a Arc<Term>) -> Display<'a>
+        where Self: Sized {
+        Display::new(self, fact, term)
+    }
+}
+
+/// Show a term with a writer when it is formatted.
+pub struct Display<'a> {
+    /// The writer.
+    writer: &'a dyn TermWriter,
+    /// The factory that made the term.
+    fact: &'a TermFactory,
+    /// The term to show.
+    term: &'a Arc<Term>,
+}
+
+impl<'a> Display<'a> {
+    /// Make a new adapter.
+    pub fn new(writer: &'a dyn TermWriter, fact: &'a TermFactory, term: &'a Arc<Term>) -> Self {
+        Display { writer, fact, term }
+    }
+}
+
+impl<'a> fmt::Display for Display<'a> {
+    fn fmt(&self, form: &mut fmt::Formatter) -> fmt::Result {
+        self.writer.write_fmt_to(form, self.fact, self.term)
+    }
 }
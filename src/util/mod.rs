@@ -17,37 +17,59 @@
 //! that is part of this distribution.  This file may not be copied,
 //! modified, or distributed except according to those terms.
 
+pub mod pretty;     // Lay out text to fit a line width.
+
+use std::fmt;
+
 /// Given a string and a "border" character, properly escape special characters in the string.
 /// Special characters are exactly the following.
 ///
 /// * nul (U+0000) becomes \0
 /// * tabulator (U+0009) becomes \t
-/// * newline (U+000A) becomes \a
+/// * newline (U+000A) becomes \n
 /// * carriage return (U+000D) becomes \r
 /// * backslash or reverse solidus (U+005C) becomes \\
 ///
 /// Additionally characters in the range 0x80 - 0xFF are rendered as \xHH, where HH are the two
 /// hexadecimal characters of the character value.  Characters above 0x0000FF are rendered using
-/// the Rust standard of \u{HHHHHH}, where HHHHHH are up to six hexadecimal digits.
+/// the Rust standard of \u{HHHH}, where HHHH are four to six hexadecimal digits.
 ///
-/// Finally, if the character specified by `border` is found, it is also escaped.
+/// Finally, if the character specified by `border` is found, it is also escaped.  An ASCII
+/// border becomes a backslash followed by the border, unless the border is one of the letters
+/// that start an escape (`0`, `t`, `n`, `r`, `x`, or `u`), in which case it becomes \xHH.  The
+/// result contains only ASCII characters.
 ///
-/// The escaped string is returned.  Note that hex digits are capitalized.
-pub fn escape(input: &String, border: char) -> (String, bool) {
+/// The escaped string is returned.  Note that hex digits are capitalized.  The original string
+/// can be recovered with `unescape`.
+pub fn escape(input: &str, border: char) -> (String, bool) {
+    escape_with(input, border, true)
+}
+
+/// Escape special characters as `escape` does, but if `ascii` is false, leave characters above
+/// 0x7F alone unless they are control characters.
+pub fn escape_with(input: &str, border: char, ascii: bool) -> (String, bool) {
     let mut output = String::new();
     let mut fixed = false;
     for ch in input.chars() {
-        if ch == border {
-            output.push_str("\\");
-            output.push(border);
+        if ch == border && (border.is_ascii() || !ascii) {
+            if "0tnrxu".contains(border) {
+                output.push_str(format!("\\x{:02X}", ch as u8).as_str());
+            } else {
+                output.push('\\');
+                output.push(border);
+            }
             fixed = true;
             continue;
         }
+        if (ch as u32) > 127 && !ascii && !ch.is_control() {
+            output.push(ch);
+            continue;
+        }
         if (ch as u32) > 127 {
             if (ch as u32) < 256 {
-                output.push_str(format!("\\x{:2X}", ch as u8).as_str());
+                output.push_str(format!("\\x{:02X}", ch as u8).as_str());
             } else {
-                output.push_str(format!("\\u{{{:4X}}}", ch as u32).as_str());
+                output.push_str(format!("\\u{{{:04X}}}", ch as u32).as_str());
             }
             fixed = true;
             continue;
@@ -63,3 +85,124 @@ pub fn escape(input: &String, border: char) -> (String, bool) {
     }
     (output, fixed)
 }
+
+/// A malformed escape found by `unescape`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnescapeError {
+    /// The offset, in characters, of the start of the problem.
+    pub offset: usize,
+    /// The number of characters that make up the problem.
+    pub width: usize,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl fmt::Display for UnescapeError {
+    fn fmt(&self, form: &mut fmt::Formatter) -> fmt::Result {
+        write!(form, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl ::std::error::Error for UnescapeError {}
+
+/// Reverse `escape` (or `escape_with`) for the same border, so that
+/// `unescape(&escape(s, border).0, border)` is `s`.  Only the escapes that `escape` produces
+/// are accepted: hexadecimal digits must be uppercase, \xHH is only for characters from 0x80
+/// to 0xFF and for the border, and \u{HHHH} is only for characters above 0xFF, with four to six
+/// digits and no more leading zeros than that.  An unescaped border is an error, as is a
+/// backslash that does not start an escape.
+pub fn unescape(input: &str, border: char) -> Result<String, UnescapeError> {
+    let chars: Vec<char> = input.chars().collect();
+    let error = |offset: usize, width: usize, message: &str| UnescapeError {
+        offset,
+        width,
+        message: message.to_string(),
+    };
+    let mut output = String::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let start = index;
+        let ch = chars[index];
+        index += 1;
+        if ch != '\\' {
+            if ch == border {
+                return Err(error(start, 1, "unescaped border"));
+            }
+            output.push(ch);
+            continue;
+        }
+        let kind = match chars.get(index) {
+            Some(&kind) => kind,
+            None => return Err(error(start, 1, "incomplete escape")),
+        };
+        index += 1;
+        let value = match kind {
+            '0' => '\0',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            '\\' => '\\',
+            'x' => {
+                let digits: String = chars[index..].iter().take(2).collect();
+                if digits.len() != 2 || !digits.chars().all(|ch| ch.is_ascii_hexdigit()) {
+                    let width = 2 + digits.chars().take_while(|ch| ch.is_ascii_hexdigit()).count();
+                    return Err(error(start, width, "\\x must be followed by two hex digits"));
+                }
+                index += 2;
+                // The digits were checked above, and two hex digits always fit in a byte.
+                let value = char::from(u8::from_str_radix(&digits, 16).unwrap_or(0));
+                if digits != format!("{:02X}", value as u8) {
+                    return Err(error(start, 4, "hex digits must be uppercase"));
+                }
+                let letter = value == border && "0tnrxu".contains(border);
+                if !value.is_ascii() || letter {
+                    value
+                } else {
+                    return Err(error(start, 4,
+                        "\\x is only used for characters from 80 to FF and for the border"));
+                }
+            }
+            'u' => {
+                if chars.get(index) != Some(&'{') {
+                    return Err(error(start, 2, "\\u must be followed by {"));
+                }
+                index += 1;
+                let mut digits = String::new();
+                while let Some(&digit) = chars.get(index) {
+                    if !digit.is_ascii_hexdigit() {
+                        break;
+                    }
+                    digits.push(digit);
+                    index += 1;
+                }
+                if chars.get(index) != Some(&'}') {
+                    return Err(error(start, index - start, "\\u{ must be closed by }"));
+                }
+                index += 1;
+                let width = index - start;
+                if digits.len() < 4 || digits.len() > 6 {
+                    return Err(error(start, width, "\\u{} must contain four to six hex digits"));
+                }
+                let value = u32::from_str_radix(&digits, 16).unwrap_or(0);
+                if digits.to_uppercase() != digits {
+                    return Err(error(start, width, "hex digits must be uppercase"));
+                }
+                if digits != format!("{:04X}", value) {
+                    return Err(error(start, width, "\\u{} has too many leading zeros"));
+                }
+                if value < 0x100 {
+                    return Err(error(start, width,
+                        "\\u{} is only used for characters above FF"));
+                }
+                match ::std::char::from_u32(value) {
+                    Some(value) => value,
+                    None => return Err(error(start, width, "not a Unicode scalar value")),
+                }
+            }
+            kind if kind == border && !"0tnrxu".contains(kind) => border,
+            _ => return Err(error(start, 2, "unknown escape")),
+        };
+        output.push(value);
+    }
+    Ok(output)
+}
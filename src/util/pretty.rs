@@ -0,0 +1,153 @@
+//! Lay out text to fit a line width.
+//!
+//! ```text
+//!           _ _     _
+//!  _ __ ___| (_)___(_) ___  _ __
+//! | '__/ _ \ | / __| |/ _ \| '_ \
+//! | | |  __/ | \__ \ | (_) | | | |
+//! |_|  \___|_|_|___/_|\___/|_| |_|
+//! ```
+//! The relision term rewriting library.
+//!
+//! # License
+//!
+//! Copyright (c) 2015 by Stacy Prowell.  All rights reserved.
+//!
+//! Licensed under the BSD 2-Clause license.  See the file LICENSE
+//! that is part of this distribution.  This file may not be copied,
+//! modified, or distributed except according to those terms.
+
+/*
+This is the pretty printer described by Philip Wadler in "A prettier printer."  A document is
+built from text, line breaks, nesting, and groups.  When a document is rendered, each group is
+laid out on a single line if it fits in the remaining width, and otherwise every line break
+directly inside the group becomes a newline followed by the current indentation.  Groups nested
+inside a broken group are considered separately.
+*/
+
+/// A document to lay out.
+#[derive(Clone, Debug)]
+pub enum Doc {
+    /// Nothing.
+    Nil,
+    /// Text, which must not contain newlines.
+    Text(String),
+    /// A line break.  If the enclosing group fits on one line, the given text is used instead.
+    Line(&'static str),
+    /// Increase the indentation of the line breaks in the document.
+    Nest(usize, Box<Doc>),
+    /// Lay out the document on one line if it fits.
+    Group(Box<Doc>),
+    /// A sequence of documents.
+    Concat(Vec<Doc>),
+}
+
+impl Doc {
+    /// Make a document from text.
+    pub fn text<S: Into<String>>(text: S) -> Doc {
+        Doc::Text(text.into())
+    }
+
+    /// Make a line break that is a space when not broken.
+    pub fn line() -> Doc {
+        Doc::Line(" ")
+    }
+
+    /// Make a line break that is nothing when not broken.
+    pub fn softline() -> Doc {
+        Doc::Line("")
+    }
+
+    /// Indent the line breaks in a document.
+    pub fn nest(indent: usize, doc: Doc) -> Doc {
+        Doc::Nest(indent, Box::new(doc))
+    }
+
+    /// Make a group.
+    pub fn group(doc: Doc) -> Doc {
+        Doc::Group(Box::new(doc))
+    }
+
+    /// Make a sequence of documents.
+    pub fn concat(docs: Vec<Doc>) -> Doc {
+        Doc::Concat(docs)
+    }
+
+    /// Lay out the document to fit the given width, if possible.
+    pub fn render(&self, width: usize) -> String {
+        let width = width.min(isize::MAX as usize);
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack: Vec<(usize, bool, &Doc)> = vec![(0, false, self)];
+        while let Some((indent, flat, doc)) = stack.pop() {
+            match *doc {
+                Doc::Nil => {}
+                Doc::Text(ref text) => {
+                    out.push_str(text);
+                    column += text.chars().count();
+                }
+                Doc::Line(alternative) => {
+                    if flat {
+                        out.push_str(alternative);
+                        column += alternative.len();
+                    } else {
+                        out.push('\n');
+                        for _ in 0..indent {
+                            out.push(' ');
+                        }
+                        column = indent;
+                    }
+                }
+                Doc::Nest(more, ref inner) => stack.push((indent + more, flat, inner)),
+                Doc::Group(ref inner) => {
+                    let fits = flat || fits(width as isize - column as isize,
+                        (indent, true, inner), &stack);
+                    stack.push((indent, fits, inner));
+                }
+                Doc::Concat(ref docs) => {
+                    for inner in docs.iter().rev() {
+                        stack.push((indent, flat, inner));
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Determine if the rest of the current line fits in the given width, assuming the first
+/// document is laid out flat.  The rest of the line is made up of the first document and the
+/// documents still on the stack, up to the first line break that is not flat.
+fn fits(mut remaining: isize, first: (usize, bool, &Doc), stack: &[(usize, bool, &Doc)]) -> bool {
+    let mut work = vec![first];
+    let mut rest = stack.iter().rev();
+    loop {
+        if remaining < 0 {
+            return false;
+        }
+        let (indent, flat, doc) = match work.pop() {
+            Some(item) => item,
+            None => match rest.next() {
+                Some(&item) => item,
+                None => return true,
+            },
+        };
+        match *doc {
+            Doc::Nil => {}
+            Doc::Text(ref text) => remaining -= text.chars().count() as isize,
+            Doc::Line(alternative) => {
+                if !flat {
+                    return true;
+                }
+                remaining -= alternative.len() as isize;
+            }
+            Doc::Nest(more, ref inner) => work.push((indent + more, flat, inner)),
+            Doc::Group(ref inner) => work.push((indent, flat, inner)),
+            Doc::Concat(ref docs) => {
+                for inner in docs.iter().rev() {
+                    work.push((indent, flat, inner));
+                }
+            }
+        }
+    }
+}
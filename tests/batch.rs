@@ -0,0 +1,90 @@
+//! Check that the repl binary evaluates its input without interaction and reports errors in its
+//! exit status.
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Run the binary with the given arguments and standard input.
+fn run(arguments: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_repl"))
+        .args(arguments)
+        .env_remove("RELISION_PATH")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The binary may exit without reading its input, as it does for expressions.
+    let _ = child.stdin.take().unwrap().write_all(input.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn expressions_are_evaluated() {
+    let output = run(&["--no-banner", "-e", "rule a -> b; a * c", "-e", "c * a"], "ignored");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  -> b * c\n  -> c * b\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn scripts_are_read_from_standard_input() {
+    let output = run(&["-q"], "root N;\nrule $x: N -> z;\nq: N * a;\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  -> z * a\n");
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn errors_set_the_exit_status() {
+    let output = run(&["-q"], "a;\nb * (;\nc;\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  -> a\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("<stdin>:2:6"));
+    assert_eq!(output.status.code(), Some(1));
+    let output = run(&["-q", "-e", "set rewrite_limit = 1; rule $x * $y -> $y * $x; a * b"], "");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("limit of 1 rewrites"));
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(run(&["--no-such-switch"], "").status.code(), Some(2));
+}
+
+#[test]
+fn batch_mode_prints_no_banner() {
+    let output = run(&["-e", "a"], "");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  -> a\n");
+    let output = run(&[], "b;\n");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  -> b\n");
+}
+
+#[test]
+fn missing_files_on_the_command_line_are_marked() {
+    let output = run(&["no-such-file.eli"], "");
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(errors.contains(" --> <command line>:1:1\n"), "{}", errors);
+    assert!(errors.contains("1 | no-such-file.eli\n  | ^^^^^^^^^^^^^^^^ not found\n"),
+        "{}", errors);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn colon_commands_are_rejected_in_batch_mode() {
+    let output = run(&["-e", "a", "-e", "  :set parens full"], "");
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(errors.contains("error: the command :set cannot be used in batch mode\n --> 2:3\n"),
+        "{}", errors);
+    assert!(errors.contains("  ^^^^ colon command\n"), "{}", errors);
+    assert!(errors.contains("use a load or set statement instead"), "{}", errors);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "  -> a\n");
+    assert_eq!(output.status.code(), Some(1));
+    let output = run(&[], "a;\n(b *\n :c);\n:load x.eli\nd;\n");
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(errors.contains("the command :load cannot be used in batch mode\n --> <stdin>:4:1\n"),
+        "{}", errors);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "");
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn interactive_mode_runs_colon_commands_from_standard_input() {
+    let output = run(&["-i", "-q"], "root N;\n:type a: N\n");
+    let text = String::from_utf8_lossy(&output.stdout);
+    assert!(text.contains("  : N\n"), "{}", text);
+    assert_eq!(output.status.code(), Some(0));
+}
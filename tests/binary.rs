@@ -0,0 +1,204 @@
+//! Check the binary form itself: the header, the sharing of nodes and file names, and the
+//! rejection of data that is truncated, corrupt, or nested too deeply.
+
+extern crate relision;
+
+use std::sync::Arc;
+use relision::error::Error;
+use relision::terms::{BinaryReader, BinaryWriter, Locus, Term, TermFactory};
+
+// The parts of the format that the tests build by hand.
+const HEADER: &[u8] = b"RELB\x01\x00";
+const LOCI_HEADER: &[u8] = b"RELB\x01\x01";
+const TAG_ROOT: u8 = 0;
+const TAG_SYMBOL: u8 = 4;
+const TAG_STATIC_PRODUCT: u8 = 9;
+const LOCUS_NEW_FILE: u8 = 2;
+const LOCUS_FILE: u8 = 3;
+
+/// Write a term in binary form.
+fn write(fact: &TermFactory, term: &Arc<Term>, loci: bool) -> Vec<u8> {
+    let mut out = vec![];
+    BinaryWriter::new().with_loci(loci).write(&mut out, fact, term).unwrap();
+    out
+}
+
+/// Read a term from bytes.
+fn read(fact: &TermFactory, bytes: &[u8]) -> Result<Arc<Term>, Error> {
+    BinaryReader::read(&mut &bytes[..], fact)
+}
+
+/// Read bytes that must be rejected, and return the message of the error.
+fn rejected(fact: &TermFactory, bytes: &[u8]) -> String {
+    match read(fact, bytes) {
+        Err(Error::Parse(diagnostic)) => diagnostic.message.clone(),
+        other => panic!("expected a parse error for {:?}, got {:?}", bytes, other),
+    }
+}
+
+/// Count the places where `part` occurs in `bytes`.
+fn occurrences(bytes: &[u8], part: &[u8]) -> usize {
+    bytes.windows(part.len()).filter(|window| *window == part).count()
+}
+
+/// Build the bytes of a term from a header, a node count, and the nodes.
+fn build(header: &[u8], count: u8, nodes: &[u8]) -> Vec<u8> {
+    let mut bytes = header.to_vec();
+    bytes.push(count);
+    bytes.extend_from_slice(nodes);
+    bytes
+}
+
+#[test]
+fn the_header_is_magic_version_and_flags() {
+    let fact = TermFactory::new();
+    assert_eq!(write(&fact, &fact.get_root(), false), build(HEADER, 1, &[TAG_ROOT]));
+    assert_eq!(write(&fact, &fact.get_root(), true), build(LOCI_HEADER, 1, &[TAG_ROOT]));
+    assert!(Arc::ptr_eq(&read(&fact, &build(HEADER, 1, &[TAG_ROOT])).unwrap(),
+        &fact.get_root()));
+}
+
+#[test]
+fn bad_headers_are_rejected() {
+    let fact = TermFactory::new();
+    let message = "this is not a binary relision term";
+    assert_eq!(rejected(&fact, b""), message);
+    assert_eq!(rejected(&fact, b"RELB\x01"), message);
+    assert_eq!(rejected(&fact, &build(b"RELX\x01\x00", 1, &[TAG_ROOT])), message);
+    assert_eq!(rejected(&fact, b"{\"kind\":\"root\"}"), message);
+    for &version in &[0u8, 2, 255] {
+        let bytes = build(&[b'R', b'E', b'L', b'B', version, 0], 1, &[TAG_ROOT]);
+        assert_eq!(rejected(&fact, &bytes),
+            format!("binary version {} is not the supported version 1", version));
+    }
+    assert_eq!(rejected(&fact, &build(HEADER, 0, &[])), "there are no nodes");
+}
+
+#[test]
+fn a_shared_subterm_is_written_once_and_read_back_shared() {
+    let fact = TermFactory::new();
+    let shared = fact.new_string(Locus::Internal, "only once".to_string());
+    let pair = fact.new_static_product(Locus::Internal, &shared, &shared);
+    let bytes = write(&fact, &pair, false);
+    assert_eq!(occurrences(&bytes, b"only once"), 1);
+    let back = read(&fact, &bytes).unwrap();
+    assert_eq!(back, pair);
+    match *back {
+        Term::StaticProduct { ref lhs, ref rhs, .. } => assert!(Arc::ptr_eq(lhs, rhs)),
+        _ => panic!("not a product: {:?}", back),
+    }
+}
+
+#[test]
+fn equal_subterms_that_are_not_shared_are_written_twice() {
+    let fact = TermFactory::new();
+    let lhs = fact.new_string(Locus::Internal, "twice".to_string());
+    let rhs = fact.new_string(Locus::Internal, "twice".to_string());
+    let bytes = write(&fact, &fact.new_static_product(Locus::Internal, &lhs, &rhs), false);
+    assert_eq!(occurrences(&bytes, b"twice"), 2);
+}
+
+#[test]
+fn a_file_name_is_written_once_and_then_referred_to() {
+    let fact = TermFactory::new();
+    let file = "some-file.eli";
+    let lhs = fact.new_symbol(Locus::File(file.to_string(), 1, 2), "a".to_string());
+    let rhs = fact.new_symbol(Locus::File(file.to_string(), 3, 4), "b".to_string());
+    let pair = fact.new_static_product(Locus::File(file.to_string(), 1, 1), &lhs, &rhs);
+    let bytes = write(&fact, &pair, true);
+    assert_eq!(occurrences(&bytes, file.as_bytes()), 1);
+    assert_eq!(occurrences(&bytes, &[LOCUS_FILE, 0, 3, 4]), 1);
+    let back = read(&fact, &bytes).unwrap();
+    assert_eq!(back, pair);
+    match *back {
+        Term::StaticProduct { ref lhs, ref rhs, .. } => {
+            assert_eq!(fact.get_locus(lhs), Locus::File(file.to_string(), 1, 2));
+            assert_eq!(fact.get_locus(rhs), Locus::File(file.to_string(), 3, 4));
+        }
+        _ => panic!("not a product: {:?}", back),
+    }
+}
+
+#[test]
+fn truncated_data_is_rejected_at_every_tag() {
+    let fact = TermFactory::new();
+    let mut terms = vec![fact.get_root(), fact.get_any(), fact.new_boolean(true),
+        fact.new_boolean(false)];
+    let locus = Locus::File("f.eli".to_string(), 1, 2);
+    let param = fact.new_variable(locus.clone(), &fact.get_any(), "x".to_string(),
+        &fact.new_boolean(true));
+    terms.push(fact.new_symbol(locus.clone(), "s".to_string()));
+    terms.push(fact.new_string(locus.clone(), "w".to_string()));
+    terms.push(fact.new_typed_boolean(locus.clone(), true, fact.get_string()));
+    terms.push(param.clone());
+    terms.push(fact.new_static_map(locus.clone(), &fact.get_string(), &fact.get_symbol()));
+    terms.push(fact.new_static_product(locus.clone(), &param, &param));
+    terms.push(fact.new_lambda(locus, &param, &param, &fact.new_boolean(false)));
+    for term in &terms {
+        for &loci in &[true, false] {
+            let good = write(&fact, term, loci);
+            assert!(read(&fact, &good).is_ok());
+            for length in 6..good.len() {
+                assert_eq!(rejected(&fact, &good[..length]), "unexpected end of the data",
+                    "{:?} cut to {} bytes", term, length);
+            }
+            let mut longer = good.clone();
+            longer.push(0);
+            assert_eq!(rejected(&fact, &longer), "unexpected data after the last node");
+        }
+    }
+}
+
+#[test]
+fn corrupt_data_is_rejected() {
+    let fact = TermFactory::new();
+    assert_eq!(rejected(&fact, &build(HEADER, 1, &[99])), "unknown node tag 99");
+    assert_eq!(rejected(&fact, &build(HEADER, 1, &[TAG_SYMBOL, 0, 1, b'a'])),
+        "reference to node 0 before it is defined");
+    assert_eq!(rejected(&fact, &build(HEADER, 2, &[TAG_ROOT, TAG_SYMBOL, 1, 1, b'a'])),
+        "reference to node 1 before it is defined");
+    assert_eq!(rejected(&fact, &build(LOCI_HEADER, 2, &[TAG_ROOT, TAG_SYMBOL, 9, 0, 1, b'a'])),
+        "unknown locus tag 9");
+    assert_eq!(rejected(&fact, &build(LOCI_HEADER, 2,
+        &[TAG_ROOT, TAG_SYMBOL, LOCUS_FILE, 0, 1, 1, 0, 1, b'a'])), "unknown file index 0");
+    assert!(read(&fact, &build(LOCI_HEADER, 2,
+        &[TAG_ROOT, TAG_SYMBOL, LOCUS_NEW_FILE, 1, b'f', 1, 1, 0, 1, b'a'])).is_ok());
+    assert_eq!(rejected(&fact, &build(HEADER, 2, &[TAG_ROOT, TAG_SYMBOL, 0, 2, 0xff, 0xfe])),
+        "string is not valid UTF-8");
+    assert_eq!(rejected(&fact, &build(HEADER, 1,
+        &[TAG_SYMBOL, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01])),
+        "integer is too large");
+}
+
+/// Build the bytes of a chain of static products `depth` nodes deep, each with the previous
+/// product on the left and the root on the right.
+fn chain(depth: usize) -> Vec<u8> {
+    let mut nodes = vec![TAG_ROOT];
+    for index in 1..depth {
+        nodes.push(TAG_STATIC_PRODUCT);
+        for &child in &[index - 1, 0] {
+            let mut value = child;
+            while value >= 0x80 {
+                nodes.push((value & 0x7f) as u8 | 0x80);
+                value >>= 7;
+            }
+            nodes.push(value as u8);
+        }
+    }
+    let mut bytes = HEADER.to_vec();
+    let mut count = depth;
+    while count >= 0x80 {
+        bytes.push((count & 0x7f) as u8 | 0x80);
+        count >>= 7;
+    }
+    bytes.push(count as u8);
+    bytes.extend_from_slice(&nodes);
+    bytes
+}
+
+#[test]
+fn terms_nested_too_deeply_are_rejected() {
+    let fact = TermFactory::new();
+    assert!(read(&fact, &chain(1_000)).is_ok());
+    assert_eq!(rejected(&fact, &chain(20_000)), "the term is nested more than 10000 deep");
+}
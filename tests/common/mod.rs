@@ -0,0 +1,44 @@
+//! Helpers shared by the integration tests.  Each test file uses only some of them.
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::PathBuf;
+
+/// A small xorshift random number generator, so that failures can be reproduced from the seed.
+pub struct Random(pub u64);
+
+impl Random {
+    /// Get the next random number.
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Get a random number below the bound.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// Pick an element of a slice.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+/// Make an empty temporary directory for a test, holding the given files with the given text.
+/// The directory is named for the test file and the test, so tests can run at the same time.
+pub fn directory(group: &str, test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = ::std::env::temp_dir().join(format!("relision-{}-{}-{}", group, test,
+        ::std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    for &(name, text) in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+    dir
+}
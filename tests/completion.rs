@@ -0,0 +1,101 @@
+//! Check the completions and hints offered while a line is typed in the REPL.
+
+extern crate relision;
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use relision::completion::Completer;
+use relision::input::ScriptedSource;
+use relision::loader::Loader;
+use relision::repl::{define_commands, repl};
+use common::directory;
+
+/// Run a scripted session and get the completer for the line after it.
+fn completer(path: Vec<PathBuf>, lines: &[&str]) -> Completer {
+    let mut source = ScriptedSource::new(lines);
+    repl(define_commands(), Loader::new(path), &[], &mut source, &mut vec![]);
+    source.completer().unwrap().clone()
+}
+
+#[test]
+fn commands_are_completed() {
+    let completer = completer(vec![], &[]);
+    assert_eq!(completer.complete(":h"), vec![":help ", ":history "]);
+    assert_eq!(completer.complete(":e"), vec![":exit "]);
+    assert_eq!(completer.complete(":x"), Vec::<String>::new());
+    assert_eq!(completer.complete(":").len(), 11);
+    assert_eq!(completer.complete(":set pa"), Vec::<String>::new());
+}
+
+#[test]
+fn names_in_terms_are_completed() {
+    let completer = completer(vec![], &["root NAT;", "root NUMBER;", "operator succ: NAT => NAT;"]);
+    assert_eq!(completer.complete("a * N"), vec!["a * NAT", "a * NONE", "a * NUMBER"]);
+    assert_eq!(completer.complete("su"), vec!["succ"]);
+    assert_eq!(completer.complete(":type su"), vec![":type succ"]);
+    assert_eq!(completer.complete("a * "), Vec::<String>::new());
+    assert_eq!(completer.complete("$N"), Vec::<String>::new());
+}
+
+#[test]
+fn results_are_completed() {
+    let completer = completer(vec![], &["a", "b", "c"]);
+    assert_eq!(completer.complete("f($"), vec!["f($_", "f($_1", "f($_2", "f($_3"]);
+    assert_eq!(completer.complete("$_2"), vec!["$_2"]);
+    assert_eq!(completer.complete("_"), Vec::<String>::new());
+}
+
+#[test]
+fn paths_after_load_are_completed() {
+    let dir = directory("completion", "paths",
+        &[("lib/nat.eli", ""), ("lib/my rules.eli", ""), ("lib/.hidden", ""), ("top.eli", "")]);
+    let completer = completer(vec![dir.clone()], &[]);
+    assert_eq!(completer.complete(":load li"), vec![":load lib/"]);
+    assert_eq!(completer.complete(":load lib/"),
+        vec![":load \"lib/my rules.eli\"", ":load lib/nat.eli"]);
+    assert_eq!(completer.complete(":load \"lib/my"), vec![":load \"lib/my rules.eli\""]);
+    assert_eq!(completer.complete(":load \"lib/my "), vec![":load \"lib/my rules.eli\""]);
+    let absolute = format!("{}/t", dir.display());
+    assert_eq!(completer.complete(&format!(":load {}", absolute)),
+        vec![format!(":load {}op.eli", absolute)]);
+    assert_eq!(completer.complete(":load lib/nat.eli "), Vec::<String>::new());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn hints_show_the_usage() {
+    let completer = completer(vec![], &[]);
+    assert_eq!(completer.hint(":load"), Some(" PATH".to_string()));
+    assert_eq!(completer.hint(":load "), Some("PATH".to_string()));
+    assert_eq!(completer.hint(":?"), Some(" [COMMAND]".to_string()));
+    assert_eq!(completer.hint(":quit"), None);
+    assert_eq!(completer.hint(":lo"), None);
+    assert_eq!(completer.hint(":load a"), None);
+    assert_eq!(completer.hint("a"), None);
+}
+
+#[test]
+fn commands_after_help_are_completed() {
+    let completer = completer(vec![], &[]);
+    assert_eq!(completer.complete(":help h"), vec![":help help", ":help history"]);
+    assert_eq!(completer.complete(":? lo"), vec![":? load"]);
+    assert_eq!(completer.complete(":help  q"), vec![":help  quit"]);
+    assert_eq!(completer.complete(":help ").len(), 11);
+    assert_eq!(completer.complete(":help load x"), Vec::<String>::new());
+    assert_eq!(completer.complete(":help x"), Vec::<String>::new());
+}
+
+#[test]
+fn usage_is_offered_when_there_is_nothing_to_complete() {
+    let completer = completer(vec![], &[]);
+    assert_eq!(completer.candidates(":type "), vec![":type TERM"]);
+    assert_eq!(completer.candidates(":set "), vec![":set [NAME VALUE]"]);
+    assert_eq!(completer.candidates(":h"), vec![":help ", ":history "]);
+    assert_eq!(completer.candidates(":? l"), vec![":? load"]);
+    assert_eq!(completer.candidates(":load"), vec![":load "]);
+    assert_eq!(completer.candidates(":quit "), Vec::<String>::new());
+    assert_eq!(completer.candidates(":type a"), Vec::<String>::new());
+    assert_eq!(completer.candidates("a"), Vec::<String>::new());
+}
@@ -0,0 +1,123 @@
+//! Check how diagnostics are rendered, and the diagnostics for types that are not types.
+
+extern crate relision;
+
+use relision::diagnostics::{Diagnostic, Sources};
+use relision::terms::{EliParser, Locus, Universe};
+use relision::Error;
+
+/// Make the sources used by the tests.
+fn sources() -> Sources {
+    let mut sources = Sources::new();
+    sources.add_console(3, ":frob now");
+    sources.add_console(99, "a * b\n\tc * d");
+    sources.add_file("lib.eli", "root N;\nrule a -> b;\n");
+    sources
+}
+
+/// Make a diagnostic with labels on several lines and in several files.
+fn mixed() -> Diagnostic {
+    Diagnostic::warning("mixed")
+        .with_primary(Locus::Console(100, 4), 1, "here")
+        .with_secondary(Locus::Console(99, 1), 5, "first")
+        .with_secondary(Locus::Console(100, 2), 0, "")
+        .with_secondary(Locus::File("lib.eli".to_string(), 2, 6), 1, "declared")
+        .with_secondary(Locus::File("gone.eli".to_string(), 7, 1), 1, "lost")
+        .with_note("a note")
+}
+
+#[test]
+fn the_primary_label_is_underlined_with_carets() {
+    let diagnostic = Diagnostic::error("the command :frob was not recognized")
+        .with_primary(Locus::Console(3, 1), 5, "unknown command")
+        .with_note("use :help to list the commands");
+    assert_eq!(diagnostic.render(&sources()), "\
+error: the command :frob was not recognized
+ --> 3:1
+  |
+3 | :frob now
+  | ^^^^^ unknown command
+  = note: use :help to list the commands
+");
+}
+
+#[test]
+fn secondary_labels_are_underlined_with_dashes_on_their_own_lines() {
+    assert_eq!(mixed().render(&sources()), "\
+warning: mixed
+   --> 100:4
+    |
+100 | \tc * d
+    | \t  ^ here
+    | \t-
+    |
+ 99 | a * b
+    | ----- first
+   ::: lib.eli:2:6
+    |
+  2 | rule a -> b;
+    |      - declared
+    = see gone.eli:7:1: lost
+    = note: a note
+");
+}
+
+#[test]
+fn colour_uses_ansi_escapes() {
+    let text = mixed().render_ansi(&sources());
+    assert!(text.starts_with("\x1b[1;33mwarning\x1b[0m\x1b[1m: mixed\x1b[0m\n\
+                              \x20  \x1b[1;34m-->\x1b[0m 100:4\n\
+                              \x20   \x1b[1;34m|\x1b[0m\n\
+                              \x1b[1;34m100 |\x1b[0m \tc * d\n\
+                              \x20   \x1b[1;34m|\x1b[0m \t  \x1b[1;33m^ here\x1b[0m\n"),
+        "{:?}", text);
+    assert!(text.ends_with("\x20   \x1b[1m= note:\x1b[0m a note\n"), "{:?}", text);
+    let plain: String = text.split('\x1b').enumerate()
+        .map(|(index, part)| if index == 0 { part } else { &part[part.find('m').unwrap() + 1..] })
+        .collect();
+    assert_eq!(plain, mixed().render(&sources()));
+}
+
+#[test]
+fn internal_loci_have_no_snippet() {
+    let diagnostic = Diagnostic::error("internal").with_primary(Locus::Internal, 3, "x")
+        .with_secondary(Locus::Internal, 1, "y");
+    assert_eq!(diagnostic.render(&sources()), "error: internal\n");
+}
+
+/// Perform some text in a new universe, and render the first error.
+fn first_error(text: &str) -> String {
+    let mut universe = Universe::new();
+    let mut sources = Sources::new();
+    sources.add_console(1, text);
+    let mut parser = EliParser::new(text, Locus::Console(1, 1));
+    loop {
+        let statement = parser.next_statement(universe.factory()).unwrap().unwrap();
+        match universe.execute(statement) {
+            Ok(_) => {}
+            Err(err @ Error::Type(_)) => return err.into_diagnostic().render(&sources),
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
+
+#[test]
+fn types_must_be_declared() {
+    assert_eq!(first_error("root N; a: N; $x: M"), "\
+error: M is not a type
+ --> 1:19
+  |
+1 | root N; a: N; $x: M
+  |                   ^ not a declared root
+  |               -- given the type here
+  = note: declare it with `root M;`
+");
+    assert_eq!(first_error("root A; operator f: A => (A * B);"), "\
+error: B is not a type
+ --> 1:31
+  |
+1 | root A; operator f: A => (A * B);
+  |                               ^ not a declared root
+  = note: declare it with `root B;`
+");
+}
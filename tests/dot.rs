@@ -0,0 +1,76 @@
+//! Check the graphs written for terms.
+
+extern crate relision;
+
+use std::sync::Arc;
+use relision::terms::{DotWriter, Locus, ShowTypes, Term, TermFactory, TermWriter, WriterConfig};
+
+/// Write a term as a graph.
+fn graph(writer: &DotWriter, fact: &TermFactory, term: &Arc<Term>) -> String {
+    let mut out = vec![];
+    writer.write(&mut out, fact, term).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Get the lines of a graph that are edges.
+fn edges(graph: &str) -> Vec<&str> {
+    graph.lines().filter(|line| line.contains(" -> ")).map(str::trim).collect()
+}
+
+/// Make the product of a variable with itself, so the variable is shared.
+fn square(fact: &TermFactory) -> Arc<Term> {
+    let x = fact.new_variable(Locus::Internal, &fact.get_any(), "x".to_string(),
+        &fact.new_boolean(true));
+    fact.new_static_product(Locus::Internal, &x, &x)
+}
+
+#[test]
+fn shared_subterms_are_one_node() {
+    let fact = TermFactory::new();
+    let text = graph(&DotWriter::new(), &fact, &square(&fact));
+    assert_eq!(text, "digraph term {\n\
+        \x20   node [shape=box, fontname=\"monospace\"];\n\
+        \x20   n0 [label=\"static product\"];\n\
+        \x20   n1 [label=\"variable\\n$x\"];\n\
+        \x20   n2 [label=\"boolean\\ntrue\"];\n\
+        \x20   n1 -> n2 [label=\"guard\", color=\"red\", fontcolor=\"red\"];\n\
+        \x20   n0 -> n1 [label=\"lhs\", color=\"darkorange\", fontcolor=\"darkorange\"];\n\
+        \x20   n0 -> n1 [label=\"rhs\", color=\"brown\", fontcolor=\"brown\"];\n\
+        }");
+}
+
+#[test]
+fn edges_are_labeled_by_role() {
+    let fact = TermFactory::new();
+    let x = fact.new_variable(Locus::Internal, &fact.get_any(), "x".to_string(),
+        &fact.new_symbol(Locus::Internal, "ok".to_string()));
+    let map = fact.new_static_map(Locus::Internal, &x, &fact.get_string());
+    let lambda = fact.new_lambda(Locus::Internal, &x, &map, &fact.new_boolean(false));
+    let text = graph(&DotWriter::new(), &fact, &lambda);
+    // A child's edges are written before the edge to it.
+    let labels: Vec<&str> = edges(&text).iter()
+        .map(|edge| edge.split('"').nth(1).unwrap())
+        .collect();
+    assert_eq!(labels, vec!["guard", "param", "domain", "codomain", "body", "guard"]);
+    assert!(text.contains("n0 [label=\"lambda\"];"));
+    assert!(text.contains("[label=\"symbol\\nok\"];"));
+    assert!(text.contains("[label=\"boolean\\nfalse\"];"));
+}
+
+#[test]
+fn root_type_edges_can_be_left_out() {
+    let fact = TermFactory::new();
+    let mut writer = DotWriter::new();
+    writer.set_config(WriterConfig { types: ShowTypes::Always, ..WriterConfig::new() });
+    let text = graph(&writer, &fact, &square(&fact));
+    let types = edges(&text).iter().filter(|edge| edge.contains("\"type\"")).count();
+    assert_eq!(types, 4);
+    assert!(text.contains("[label=\"ANY\"];"));
+    assert!(text.contains("[label=\"^ROOT\"];"));
+    let writer = writer.with_root_types(false);
+    let text = graph(&writer, &fact, &square(&fact));
+    assert!(edges(&text).iter().all(|edge| !edge.contains("\"type\"")));
+    assert!(!text.contains("ANY"));
+    assert!(!text.contains("^ROOT"));
+    assert_eq!(edges(&text).len(), 3);
+}
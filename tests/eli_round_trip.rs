@@ -0,0 +1,312 @@
+//! Check that terms written by the ELI writer are read back as the same terms, and that
+//! parentheses are written exactly where they are needed.
+
+extern crate relision;
+
+mod common;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use relision::terms::{EliParser, EliWriter, Locus, Parens, ShowTypes, SexpWriter, Term,
+    TermFactory, TermWriter, WriterConfig};
+use common::Random;
+
+/// Names that exercise quoting: keywords, named root terms, and names that are not identifiers.
+const NAMES: &[&str] = &["x", "foo", "_bar1", "STRING", "ANY", "true", "in", "root", "two words",
+    "", "9lives", "back`tick", "caf\u{e9}", "\u{3bb}", "new\nline"];
+
+/// Text that exercises escapes.
+const TEXT: &[&str] = &["", "plain", "\"quoted\"", "tab\there", "nul\0", "back\\slash",
+    "\u{e9}t\u{e9}", "\u{3bb}x.\u{2192}", "\u{1f600}", "cr\r\n"];
+
+/// Make a random term of at most the given depth.
+fn term(fact: &TermFactory, random: &mut Random, depth: usize) -> Arc<Term> {
+    let choice = if depth == 0 { random.below(5) } else { random.below(8) };
+    match choice {
+        0 => fact.get_root(),
+        1 => {
+            let name = random.pick(NAMES).to_string();
+            let typ = typ(fact, random, depth);
+            fact.new_typed_symbol(Locus::Internal, name, typ)
+        }
+        2 => {
+            let text = random.pick(TEXT).to_string();
+            let typ = typ(fact, random, depth);
+            fact.new_typed_string(Locus::Internal, text, typ)
+        }
+        3 => {
+            let value = random.below(2) == 0;
+            let typ = typ(fact, random, depth);
+            fact.new_typed_boolean(Locus::Internal, value, typ)
+        }
+        4 => {
+            let name = random.pick(NAMES).to_string();
+            let typ = typ(fact, random, depth);
+            let guard = guard(fact, random, depth);
+            fact.new_variable(Locus::Internal, &typ, name, &guard)
+        }
+        5 => {
+            let domain = term(fact, random, depth - 1);
+            let codomain = term(fact, random, depth - 1);
+            fact.new_static_map(Locus::Internal, &domain, &codomain)
+        }
+        6 => {
+            let lhs = term(fact, random, depth - 1);
+            let rhs = term(fact, random, depth - 1);
+            fact.new_static_product(Locus::Internal, &lhs, &rhs)
+        }
+        _ => {
+            let param = term(fact, random, depth - 1);
+            let body = term(fact, random, depth - 1);
+            let guard = guard(fact, random, depth - 1);
+            fact.new_lambda(Locus::Internal, &param, &body, &guard)
+        }
+    }
+}
+
+/// Make a random type, which is usually a named root term.
+fn typ(fact: &TermFactory, random: &mut Random, depth: usize) -> Arc<Term> {
+    match random.below(8) {
+        0 if depth > 0 => term(fact, random, depth - 1),
+        1 => fact.get_root(),
+        2 => fact.get_string(),
+        3 => fact.get_boolean(),
+        4 => fact.get_any(),
+        _ => fact.get_symbol(),
+    }
+}
+
+/// Make a random guard, which is usually the default.
+fn guard(fact: &TermFactory, random: &mut Random, depth: usize) -> Arc<Term> {
+    if depth > 0 && random.below(3) == 0 {
+        term(fact, random, depth - 1)
+    } else {
+        fact.new_boolean(true)
+    }
+}
+
+/// Write a term with a writer.
+fn write(writer: &dyn TermWriter, fact: &TermFactory, term: &Arc<Term>) -> String {
+    let mut out = vec![];
+    writer.write(&mut out, fact, term).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Get a form of a term that shows its whole structure but not its loci.
+fn structure(fact: &TermFactory, term: &Arc<Term>) -> String {
+    let mut config = WriterConfig::new();
+    config.types = ShowTypes::Always;
+    write(&SexpWriter::new().with_config(config), fact, term)
+}
+
+/// Write a term in ELI form and parse it back.
+fn round_trip(fact: &TermFactory, term: &Arc<Term>, writer: &EliWriter) -> Arc<Term> {
+    let text = write(writer, fact, term);
+    match EliParser::new(&text, Locus::Internal).parse_term(fact) {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("could not parse {:?}: {}", text, err),
+    }
+}
+
+/// Write the term parsed from text with the default configuration.
+fn minimal(text: &str) -> String {
+    let fact = TermFactory::new();
+    let term = EliParser::new(text, Locus::Internal).parse_term(&fact).unwrap();
+    write(&EliWriter::new(), &fact, &term)
+}
+
+#[test]
+fn random_terms_round_trip() {
+    let fact = TermFactory::new();
+    let mut random = Random(0x5eed_1234_abcd_0001);
+    for _ in 0..500 {
+        let original = term(&fact, &mut random, 4);
+        let expected = structure(&fact, &original);
+        for &parens in &[Parens::Minimal, Parens::Full] {
+            for &(ascii, width) in &[(true, 80), (false, 20)] {
+                for &types in &[ShowTypes::WhenNeeded, ShowTypes::Always] {
+                    let config = WriterConfig { types, loci: false, parens, ascii, sharing: false };
+                    let writer = EliWriter::new().with_config(config).with_width(width);
+                    let parsed = round_trip(&fact, &original, &writer);
+                    assert_eq!(structure(&fact, &parsed), expected,
+                        "{:?}", write(&writer, &fact, &original));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn random_terms_are_stable() {
+    // Writing a term that was read back gives the same text.
+    let fact = TermFactory::new();
+    let mut random = Random(0x0bad_cafe_f00d_0002);
+    let writer = EliWriter::new();
+    for _ in 0..500 {
+        let original = term(&fact, &mut random, 4);
+        let first = write(&writer, &fact, &original);
+        let second = write(&writer, &fact, &round_trip(&fact, &original, &writer));
+        assert_eq!(first, second);
+    }
+}
+
+#[test]
+fn maps_associate_to_the_right() {
+    assert_eq!(minimal("a => b => c"), "a => b => c");
+    assert_eq!(minimal("a => (b => c)"), "a => b => c");
+    assert_eq!(minimal("(a => b) => c"), "(a => b) => c");
+}
+
+#[test]
+fn products_associate_to_the_left() {
+    assert_eq!(minimal("a * b * c"), "a * b * c");
+    assert_eq!(minimal("(a * b) * c"), "a * b * c");
+    assert_eq!(minimal("a * (b * c)"), "a * (b * c)");
+}
+
+#[test]
+fn products_bind_more_tightly_than_maps() {
+    assert_eq!(minimal("(a * b) => (c * d)"), "a * b => c * d");
+    assert_eq!(minimal("(a => b) * c"), "(a => b) * c");
+    assert_eq!(minimal("a * (b => c)"), "a * (b => c)");
+}
+
+#[test]
+fn lambdas_bind_most_loosely() {
+    assert_eq!(minimal("$x -> (a => b)"), "$x -> a => b");
+    assert_eq!(minimal("$x -> ($y -> $y)"), "$x -> $y -> $y");
+    assert_eq!(minimal("(a => b) -> c"), "a => b -> c");
+    assert_eq!(minimal("($x -> $x) -> c"), "($x -> $x) -> c");
+    assert_eq!(minimal("($x -> $x) => c"), "($x -> $x) => c");
+    assert_eq!(minimal("a => ($x -> $x)"), "a => ($x -> $x)");
+    assert_eq!(minimal("($x -> $x) * c"), "($x -> $x) * c");
+}
+
+#[test]
+fn types_bind_most_tightly() {
+    assert_eq!(minimal("$x: (a => b)"), "$x: (a => b)");
+    assert_eq!(minimal("$x: (a * b)"), "$x: (a * b)");
+    assert_eq!(minimal("$x: a * b"), "$x: a * b");
+    assert_eq!(minimal("\"s\": (t: u)"), "\"s\": t: u");
+}
+
+#[test]
+fn names_are_quoted_when_needed() {
+    assert_eq!(minimal("`two words`"), "`two words`");
+    assert_eq!(minimal("`true`"), "`true`");
+    assert_eq!(minimal("`9lives`"), "`9lives`");
+    assert_eq!(minimal("$`two words`"), "$`two words`");
+    assert_eq!(minimal("plain"), "plain");
+}
+
+/// Make a random term that shares subterms, by reusing terms made before.
+fn dag(fact: &TermFactory, random: &mut Random, pool: &mut Vec<Arc<Term>>) -> Arc<Term> {
+    let made = if pool.len() < 2 || random.below(4) == 0 {
+        term(fact, random, 1)
+    } else {
+        let left = random.pick(pool).clone();
+        let right = random.pick(pool).clone();
+        match random.below(3) {
+            0 => fact.new_static_map(Locus::Internal, &left, &right),
+            1 => fact.new_static_product(Locus::Internal, &left, &right),
+            _ => fact.new_lambda(Locus::Internal, &left, &right, &fact.new_boolean(true)),
+        }
+    };
+    pool.push(made.clone());
+    made
+}
+
+/// Count the distinct maps, products, and lambdas in a term.
+fn distinct(term: &Arc<Term>, seen: &mut HashSet<*const Term>) -> usize {
+    let children = match **term {
+        Term::StaticMap { ref domain, ref codomain, .. } => vec![domain, codomain],
+        Term::StaticProduct { ref lhs, ref rhs, .. } => vec![lhs, rhs],
+        Term::Lambda { ref param, ref body, .. } => vec![param, body],
+        _ => return 0,
+    };
+    if !seen.insert(&**term as *const Term) {
+        return 0;
+    }
+    1 + children.into_iter().map(|child| distinct(child, seen)).sum::<usize>()
+}
+
+#[test]
+fn shared_subterms_are_written_once() {
+    let fact = TermFactory::new();
+    let mut term = fact.new_symbol(Locus::Internal, "a".to_string());
+    for _ in 0..40 {
+        term = fact.new_static_product(Locus::Internal, &term, &term);
+    }
+    let mut config = WriterConfig::new();
+    config.sharing = true;
+    let writer = EliWriter::new().with_config(config);
+    let text = write(&writer, &fact, &term);
+    assert!(text.len() < 1000, "{}", text);
+    assert!(text.starts_with("let\n    #1 = a * a,\n    #2 = #1 * #1,"), "{}", text);
+
+    // Every level of the term read back is shared by its two sides, except the symbols at the
+    // bottom, which are too small to be bound.
+    let mut parsed = round_trip(&fact, &term, &writer);
+    for _ in 0..39 {
+        parsed = match *parsed {
+            Term::StaticProduct { ref lhs, ref rhs, .. } => {
+                assert!(Arc::ptr_eq(lhs, rhs));
+                lhs.clone()
+            }
+            _ => panic!("expected a product"),
+        };
+    }
+    assert_eq!(write(&EliWriter::new(), &fact, &parsed), "a * a");
+}
+
+#[test]
+fn random_dags_keep_their_sharing() {
+    let fact = TermFactory::new();
+    let mut random = Random(0xd0d0_a5a5_1357_0003);
+    let mut config = WriterConfig::new();
+    config.sharing = true;
+    for &parens in &[Parens::Minimal, Parens::Full] {
+        config.parens = parens;
+        let writer = EliWriter::new().with_config(config.clone()).with_width(40);
+        for _ in 0..50 {
+            let mut pool = vec![];
+            let mut original = dag(&fact, &mut random, &mut pool);
+            for _ in 0..random.below(12) {
+                original = dag(&fact, &mut random, &mut pool);
+            }
+            let parsed = round_trip(&fact, &original, &writer);
+            assert_eq!(structure(&fact, &parsed), structure(&fact, &original),
+                "{}", write(&writer, &fact, &original));
+            assert_eq!(distinct(&parsed, &mut HashSet::new()),
+                distinct(&original, &mut HashSet::new()),
+                "{}", write(&writer, &fact, &original));
+        }
+    }
+}
+
+#[test]
+fn bindings_are_scoped() {
+    let fact = TermFactory::new();
+    let parse = |text: &str| EliParser::new(text, Locus::Internal).parse_term(&fact);
+    assert!(parse("let #a = x * y in #a => #a").is_ok());
+    assert!(parse("let #a = x, #b = #a * #a in #b").is_ok());
+    assert!(parse("(let #a = x in #a) => #a").is_err());
+    assert!(parse("let #b = #a, #a = x in #b").is_err());
+    assert!(parse("let #a = x").is_err());
+}
+
+#[test]
+fn broken_terms_fit_and_are_indented() {
+    let fact = TermFactory::new();
+    let text = (0..12).map(|index| format!("name{}", index)).collect::<Vec<_>>().join(" * ");
+    let term = EliParser::new(&text, Locus::Internal).parse_term(&fact).unwrap();
+    for &(width, indent) in &[(30, 2), (40, 6)] {
+        let written = write(&EliWriter::new().with_width(width).with_indent(indent), &fact, &term);
+        let lines: Vec<&str> = written.lines().collect();
+        assert!(lines.len() > 1, "{}", written);
+        assert!(lines.iter().all(|line| line.len() <= width), "{}", written);
+        for line in &lines[1..] {
+            assert_eq!(line.len() - line.trim_start().len(), indent, "{}", written);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Check that `unescape` reverses `escape` and reports malformed escapes.
+
+extern crate relision;
+
+mod common;
+
+use relision::util::{escape, escape_with, unescape};
+use common::Random;
+
+/// Make a random character.  Characters that are special to `escape` are likely, but any
+/// Unicode scalar value can be chosen.
+fn char(random: &mut Random) -> char {
+    const SPECIAL: &str = "\0\t\n\r\\\"`'{}0tnrxuAF$ \u{7f}\u{80}\u{e9}\u{ff}\u{100}\u{fff}\
+        \u{1000}\u{ffff}\u{10000}\u{10ffff}";
+    let limit = match random.below(4) {
+        0 => return *random.pick(&SPECIAL.chars().collect::<Vec<char>>()),
+        1 => 0x80,
+        2 => 0x1_0000,
+        _ => 0x11_0000,
+    };
+    loop {
+        if let Some(ch) = ::std::char::from_u32(random.below(limit) as u32) {
+            return ch;
+        }
+    }
+}
+
+/// Make a random string.
+fn string(random: &mut Random) -> String {
+    let length = random.below(12);
+    (0..length).map(|_| char(random)).collect()
+}
+
+#[test]
+fn unescape_reverses_escape() {
+    let mut random = Random(0x0123_4567_89ab_cdef);
+    for _ in 0..20_000 {
+        let text = string(&mut random);
+        let border = char(&mut random);
+        let (escaped, _) = escape(&text, border);
+        assert!(escaped.is_ascii(), "{:?} escaped as {:?}", text, escaped);
+        assert_eq!(unescape(&escaped, border).as_ref(), Ok(&text),
+            "{:?} with border {:?} escaped as {:?}", text, border, escaped);
+        let (escaped, _) = escape_with(&text, border, false);
+        assert_eq!(unescape(&escaped, border).as_ref(), Ok(&text),
+            "{:?} with border {:?} escaped as {:?}", text, border, escaped);
+    }
+}
+
+#[test]
+fn escaped_text_does_not_contain_the_border() {
+    let mut random = Random(0xfedc_ba98_7654_3210);
+    for _ in 0..20_000 {
+        let text = string(&mut random);
+        let border = char(&mut random);
+        let (escaped, _) = escape(&text, border);
+        // Every border left in the text must be part of an escape, so removing the escapes
+        // must remove the border.
+        let mut chars = escaped.chars();
+        while let Some(ch) = chars.next() {
+            if ch == '\\' {
+                match chars.next() {
+                    Some('u') => while chars.next() != Some('}') {},
+                    Some('x') => {
+                        chars.next();
+                        chars.next();
+                    }
+                    _ => {}
+                }
+            } else {
+                assert!(ch != border, "{:?} with border {:?} escaped as {:?}",
+                    text, border, escaped);
+            }
+        }
+    }
+}
+
+#[test]
+fn hex_digits_are_zero_padded() {
+    assert_eq!(escape("\u{80}", '"').0, "\\x80");
+    assert_eq!(escape("\u{100}", '"').0, "\\u{0100}");
+    assert_eq!(escape("\u{fff}", '"').0, "\\u{0FFF}");
+    assert_eq!(escape("\u{10ffff}", '"').0, "\\u{10FFFF}");
+}
+
+#[test]
+fn malformed_escapes_are_reported() {
+    let check = |text: &str, offset: usize, width: usize| {
+        let err = unescape(text, '"').unwrap_err();
+        assert_eq!((err.offset, err.width), (offset, width), "{:?}: {}", text, err);
+    };
+    check("abc\\", 3, 1);
+    check("a\\q", 1, 2);
+    check("ab\\x4", 2, 3);
+    check("\\xZZ", 0, 2);
+    check("\u{e9}\\u", 1, 2);
+    check("\\u{}", 0, 4);
+    check("\\u{1234567}", 0, 11);
+    check("\\u{D800}", 0, 8);
+    check("\\u{41", 0, 5);
+    check("a\"b", 1, 1);
+}
+
+#[test]
+fn only_the_escapes_that_escape_writes_are_accepted() {
+    let check = |text: &str, border: char, offset: usize, width: usize, message: &str| {
+        let err = unescape(text, border).unwrap_err();
+        assert_eq!((err.offset, err.width, &err.message[..]), (offset, width, message),
+            "{:?}", text);
+    };
+    check("ab\\x0a", '"', 2, 4, "hex digits must be uppercase");
+    check("\\x0A", '"', 0, 4, "\\x is only used for characters from 80 to FF and for the border");
+    check("\\xe9", '"', 0, 4, "hex digits must be uppercase");
+    check("\\x74", '"', 0, 4, "\\x is only used for characters from 80 to FF and for the border");
+    assert_eq!(unescape("\\x74", 't'), Ok("t".to_string()));
+    assert_eq!(unescape("\\xE9", '"'), Ok("\u{e9}".to_string()));
+    check("a\\u{41}", '"', 1, 6, "\\u{} must contain four to six hex digits");
+    check("\\u{0041}", '"', 0, 8, "\\u{} is only used for characters above FF");
+    check("\\u{00E9}", '"', 0, 8, "\\u{} is only used for characters above FF");
+    check("\\u{0fff}", '"', 0, 8, "hex digits must be uppercase");
+    check("\\u{01000}", '"', 0, 9, "\\u{} has too many leading zeros");
+    check("\\u{010000}", '"', 0, 10, "\\u{} has too many leading zeros");
+    assert_eq!(unescape("\\u{0FFF}\\u{10000}", '"'), Ok("\u{fff}\u{10000}".to_string()));
+}
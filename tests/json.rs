@@ -0,0 +1,168 @@
+//! Check that every kind of term survives a round trip through JSON, that the JSON itself has
+//! the documented form, and that JSON that does not describe a term is rejected.
+
+extern crate relision;
+
+use std::sync::Arc;
+use relision::error::Error;
+use relision::terms::{JsonReader, JsonWriter, Locus, Term, TermFactory, TermWriter};
+
+/// Write a term as JSON.
+fn write(fact: &TermFactory, term: &Arc<Term>, loci: bool) -> String {
+    let mut out = vec![];
+    JsonWriter::new().with_loci(loci).write(&mut out, fact, term).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Read JSON that must be rejected, and return the message of the error.
+fn rejected(fact: &TermFactory, text: &str) -> String {
+    match JsonReader::read_str(text, fact) {
+        Err(Error::Parse(diagnostic)) => diagnostic.message.clone(),
+        other => panic!("expected a parse error for {:?}, got {:?}", text, other),
+    }
+}
+
+/// Write a term as JSON and read it back.
+fn round_trip(fact: &TermFactory, term: &Arc<Term>, loci: bool) -> Arc<Term> {
+    JsonReader::read_str(&write(fact, term, loci), fact).unwrap()
+}
+
+/// Check that a term comes back exactly, with and without loci.  Without loci, the term is
+/// made again with every locus internal and compared to that.
+fn check(make: &dyn Fn(&TermFactory, Locus) -> Arc<Term>) {
+    let fact = TermFactory::new();
+    let located = make(&fact, Locus::File("test.eli".to_string(), 3, 7));
+    assert_eq!(round_trip(&fact, &located, true), located);
+    let internal = make(&fact, Locus::Internal);
+    assert_eq!(round_trip(&fact, &located, false), internal);
+    assert_eq!(round_trip(&fact, &internal, true), internal);
+}
+
+#[test]
+fn root() {
+    check(&|fact, _| fact.get_root());
+}
+
+#[test]
+fn named_root() {
+    let fact = TermFactory::new();
+    let any = fact.get_any();
+    assert!(Arc::ptr_eq(&round_trip(&fact, &any, true), &any));
+}
+
+#[test]
+fn symbol() {
+    check(&|fact, locus| fact.new_symbol(locus, "fred".to_string()));
+    check(&|fact, locus| {
+        fact.new_typed_symbol(locus.clone(), "x y".to_string(),
+            fact.new_symbol(locus, "PLACE".to_string()))
+    });
+}
+
+#[test]
+fn string() {
+    check(&|fact, locus| fact.new_string(locus, "tab\there \"quoted\" \u{1}\u{1F600}".to_string()));
+}
+
+#[test]
+fn boolean() {
+    check(&|fact, locus| fact.new_typed_boolean(locus, false, fact.get_boolean()));
+    let fact = TermFactory::new();
+    let shared = fact.new_boolean(true);
+    assert!(Arc::ptr_eq(&round_trip(&fact, &shared, true), &shared));
+}
+
+#[test]
+fn variable() {
+    check(&|fact, locus| {
+        let guard = fact.new_typed_boolean(locus.clone(), true, fact.get_boolean());
+        fact.new_variable(locus, &fact.get_string(), "x".to_string(), &guard)
+    });
+}
+
+#[test]
+fn static_map() {
+    check(&|fact, locus| fact.new_static_map(locus, &fact.get_string(), &fact.get_symbol()));
+}
+
+#[test]
+fn static_product() {
+    check(&|fact, locus| {
+        let lhs = fact.new_string(locus.clone(), "a".to_string());
+        fact.new_static_product(locus, &lhs, &fact.get_symbol())
+    });
+}
+
+#[test]
+fn lambda() {
+    check(&|fact, locus| {
+        let param = fact.new_variable(locus.clone(), &fact.get_any(), "x".to_string(),
+            &fact.new_boolean(true));
+        let body = fact.new_static_product(locus.clone(), &param, &param);
+        fact.new_lambda(locus, &param, &body, &fact.new_boolean(false))
+    });
+}
+
+#[test]
+fn a_typed_symbol_is_written_with_its_type() {
+    let fact = TermFactory::new();
+    let place = fact.new_symbol(Locus::Internal, "PLACE".to_string());
+    let symbol = fact.new_typed_symbol(Locus::Internal, "x".to_string(), place);
+    assert_eq!(write(&fact, &symbol, false), "{\"kind\":\"symbol\",\
+        \"type\":{\"kind\":\"symbol\",\"value\":\"PLACE\"},\"value\":\"x\"}");
+    // The default type of a named root term is the root.
+    assert_eq!(write(&fact, &fact.get_any(), false), "{\"kind\":\"symbol\",\"value\":\"ANY\"}");
+}
+
+#[test]
+fn a_located_term_is_written_with_its_locus() {
+    let fact = TermFactory::new();
+    let file = fact.new_symbol(Locus::File("test.eli".to_string(), 3, 7), "x".to_string());
+    assert_eq!(write(&fact, &file, true), "{\"kind\":\"symbol\",\
+        \"locus\":{\"file\":\"test.eli\",\"line\":3,\"column\":7},\"value\":\"x\"}");
+    assert_eq!(write(&fact, &file, false), "{\"kind\":\"symbol\",\"value\":\"x\"}");
+    let console = fact.new_static_product(Locus::Console(2, 5), &file, &fact.get_root());
+    assert_eq!(write(&fact, &console, true), "{\"kind\":\"static_product\",\
+        \"locus\":{\"line\":2,\"column\":5},\"children\":[{\"kind\":\"symbol\",\
+        \"locus\":{\"file\":\"test.eli\",\"line\":3,\"column\":7},\"value\":\"x\"},\
+        {\"kind\":\"root\"}]}");
+}
+
+#[test]
+fn unknown_kinds_are_rejected() {
+    let fact = TermFactory::new();
+    assert_eq!(rejected(&fact, "{\"kind\":\"wibble\"}"), "unknown kind of term wibble");
+    assert_eq!(rejected(&fact, "{\"kind\":\"Symbol\",\"value\":\"x\"}"),
+        "unknown kind of term Symbol");
+    assert_eq!(rejected(&fact, "{\"kind\":7}"), "every term must have a kind");
+    assert_eq!(rejected(&fact, "{\"value\":\"x\"}"), "every term must have a kind");
+}
+
+#[test]
+fn missing_members_are_rejected() {
+    let fact = TermFactory::new();
+    assert_eq!(rejected(&fact, "{\"kind\":\"symbol\"}"), "the value must be a string");
+    assert_eq!(rejected(&fact, "{\"kind\":\"string\"}"), "the value must be a string");
+    assert_eq!(rejected(&fact, "{\"kind\":\"boolean\"}"),
+        "a boolean must have a true or false value");
+    assert_eq!(rejected(&fact, "{\"kind\":\"variable\",\"value\":\"x\"}"),
+        "a variable must have 1 child");
+    assert_eq!(rejected(&fact,
+        "{\"kind\":\"static_map\",\"children\":[{\"kind\":\"root\"}]}"),
+        "a static_map must have 2 children");
+    assert_eq!(rejected(&fact, "{\"kind\":\"lambda\"}"), "a lambda must have 3 children");
+    assert_eq!(rejected(&fact,
+        "{\"kind\":\"symbol\",\"value\":\"x\",\"locus\":{\"line\":1}}"),
+        "the column of a locus must be a non-negative integer");
+    assert_eq!(rejected(&fact,
+        "{\"kind\":\"static_map\",\"children\":[{},{\"kind\":\"root\"}]}"),
+        "every term must have a kind");
+}
+
+#[test]
+fn malformed() {
+    let fact = TermFactory::new();
+    assert!(JsonReader::read_str("{\"kind\":\"lambda\",\"children\":[]}", &fact).is_err());
+    assert!(JsonReader::read_str("{\"kind\":\"symbol\"", &fact).is_err());
+    assert!(JsonReader::read_str("[]", &fact).is_err());
+}
@@ -0,0 +1,78 @@
+//! Check that files are found, loaded in order, and not loaded in a cycle.
+
+extern crate relision;
+
+mod common;
+
+use std::fs;
+use std::path::Path;
+use relision::diagnostics::Sources;
+use relision::loader::Loader;
+use relision::terms::{Locus, Universe};
+use common::directory;
+
+/// Load a file, and collect the results of its terms as text.
+fn load(loader: &mut Loader, path: &Path) -> relision::Result<Vec<String>> {
+    let mut universe = Universe::new();
+    let mut sources = Sources::new();
+    let mut results = vec![];
+    loader.load(&mut universe, &mut sources, &path.to_string_lossy(), Locus::Internal, 0,
+        &mut |universe, term| results.push(universe.display(term).to_string()))?;
+    Ok(results)
+}
+
+#[test]
+fn files_are_found_next_to_the_loader_and_on_the_path() {
+    let dir = directory("loader", "find", &[
+        ("main.eli", "load \"sub/rules\"; load common; a * a; b;"),
+        ("sub/rules.eli", "rule $x * $x -> same;"),
+        ("lib/common.eli", "rule b -> c;"),
+    ]);
+    let mut loader = Loader::new(vec![dir.join("lib")]);
+    assert_eq!(load(&mut loader, &dir.join("main")).unwrap(), vec!["same", "c"]);
+    assert_eq!(loader.find("rules", Some(&dir)), None);
+    assert_eq!(loader.find("sub/rules", Some(&dir)), Some(dir.join("sub/rules.eli")));
+    assert_eq!(loader.find("common", None), Some(dir.join("lib").join("common.eli")));
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn cycles_are_reported() {
+    let dir = directory("loader", "cycle", &[
+        ("a.eli", "load b; x;"),
+        ("b.eli", "load c;"),
+        ("c.eli", "load a;"),
+        ("twice.eli", "load c2; load c2; y;"),
+        ("c2.eli", "z;"),
+    ]);
+    let mut loader = Loader::new(vec![]);
+    let err = load(&mut loader, &dir.join("a.eli")).unwrap_err();
+    let diagnostic = err.into_diagnostic();
+    assert!(diagnostic.message.contains("loads itself"), "{}", diagnostic.message);
+    let primary = diagnostic.primary.unwrap();
+    assert_eq!(primary.locus, Locus::File(dir.join("c.eli").to_string_lossy().into_owned(), 1, 6));
+    assert_eq!(primary.width, 1);
+    assert_eq!(primary.message, "loaded again here");
+    assert_eq!(load(&mut loader, &dir.join("twice.eli")).unwrap(), vec!["z", "z", "y"]);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn missing_files_are_reported() {
+    let mut loader = Loader::new(vec![]);
+    let err = load(&mut loader, Path::new("no/such/file.eli")).unwrap_err();
+    assert!(err.to_string().contains("cannot find the file no/such/file.eli"), "{}", err);
+}
+
+#[test]
+fn load_statements_mark_the_path() {
+    let dir = directory("loader", "mark", &[("main.eli", "x;\n  load \"no such\";")]);
+    let mut loader = Loader::new(vec![]);
+    let diagnostic = load(&mut loader, &dir.join("main.eli")).unwrap_err().into_diagnostic();
+    let primary = diagnostic.primary.unwrap();
+    assert_eq!(primary.locus,
+        Locus::File(dir.join("main.eli").to_string_lossy().into_owned(), 2, 8));
+    assert_eq!(primary.width, 9);
+    assert_eq!(primary.message, "not found");
+    let _ = fs::remove_dir_all(&dir);
+}
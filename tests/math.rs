@@ -0,0 +1,79 @@
+//! Check the mathematical notation written by the LaTeX and Unicode writers.
+
+extern crate relision;
+
+use relision::terms::{EliParser, LatexWriter, Locus, TermFactory, TermWriter, UnicodeWriter,
+    Universe, WriterConfig};
+
+/// Parse a term and write it with both writers, giving `+` as the notation for `plus`.
+fn render(text: &str) -> (String, String) {
+    let fact = TermFactory::new();
+    let term = EliParser::new(text, Locus::Internal).parse_term(&fact).unwrap();
+    let unicode = UnicodeWriter::new().with_notation("plus", "+").to_string(&fact, &term).unwrap();
+    let latex = LatexWriter::new().with_notation("plus", "+").to_string(&fact, &term).unwrap();
+    (unicode, latex)
+}
+
+#[test]
+fn maps_and_products() {
+    let (unicode, latex) = render("(a => b) * c => d");
+    assert_eq!(unicode, "(a \u{2192} b) \u{d7} c \u{2192} d");
+    assert_eq!(latex, "(\\mathtt{a} \\to \\mathtt{b}) \\times \\mathtt{c} \\to \\mathtt{d}");
+}
+
+#[test]
+fn lambdas_and_variables() {
+    let (unicode, latex) = render("$x -> $long");
+    assert_eq!(unicode, "\u{3bb}\u{1d465}. \u{1d459}\u{1d45c}\u{1d45b}\u{1d454}");
+    assert_eq!(latex, "\\lambda x.\\, \\mathit{long}");
+}
+
+#[test]
+fn guards_are_side_conditions() {
+    let (unicode, latex) = render("$x{p} -> $y{q}");
+    assert_eq!(unicode, "\u{3bb}\u{1d465}. \u{1d466}   if p, q");
+    assert_eq!(latex, "\\lambda x.\\, y \\quad\\text{if } \\mathtt{p},\\ \\mathtt{q}");
+}
+
+#[test]
+fn operators_have_their_own_notation() {
+    let (unicode, latex) = render("plus: (N * N => N)");
+    assert_eq!(unicode, "+: (N \u{d7} N \u{2192} N)");
+    assert_eq!(latex,
+        "+ \\mathbin{:} (\\mathtt{N} \\times \\mathtt{N} \\to \\mathtt{N})");
+}
+
+#[test]
+fn text_is_escaped() {
+    let (unicode, latex) = render("\"50% of $x_1\" * STRING");
+    assert_eq!(unicode, "\u{201c}50% of $x_1\u{201d} \u{d7} STRING");
+    assert_eq!(latex, "\\text{``50\\% of \\$x\\_1''} \\times \\mathsf{STRING}");
+}
+
+#[test]
+fn unicode_text_is_not_escaped_by_default() {
+    let fact = TermFactory::new();
+    let term = EliParser::new("\"caf\u{e9}\" * `\u{e9}t\u{e9}`", Locus::Internal)
+        .parse_term(&fact).unwrap();
+    assert_eq!(UnicodeWriter::new().to_string(&fact, &term).unwrap(),
+        "\u{201c}caf\u{e9}\u{201d} \u{d7} \u{e9}t\u{e9}");
+    let mut config = WriterConfig::new();
+    config.ascii = true;
+    assert_eq!(UnicodeWriter::new().with_config(config).to_string(&fact, &term).unwrap(),
+        "\u{201c}caf\\xE9\u{201d} \u{d7} `\\xE9t\\xE9`");
+}
+
+#[test]
+fn declared_operators_are_upright() {
+    let mut universe = Universe::new();
+    universe.load_from("root N; operator succ: N => N; operator plus: N * N => N;",
+        Locus::Internal).unwrap();
+    let fact = universe.factory();
+    let term = EliParser::new("succ * plus * zero", Locus::Internal).parse_term(fact).unwrap();
+    let unicode = UnicodeWriter::new().with_notation("plus", "+")
+        .with_operators(universe.operators()).to_string(fact, &term).unwrap();
+    assert_eq!(unicode, "\u{1d5cc}\u{1d5ce}\u{1d5bc}\u{1d5bc} \u{d7} + \u{d7} zero");
+    let latex = LatexWriter::new().with_notation("plus", "+")
+        .with_operators(universe.operators()).to_string(fact, &term).unwrap();
+    assert_eq!(latex, "\\operatorname{succ} \\times + \\times \\mathtt{zero}");
+}
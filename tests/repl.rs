@@ -0,0 +1,230 @@
+//! Check how the REPL reads its input, and drive it through scripted sessions.
+
+extern crate relision;
+
+mod common;
+
+use std::fs;
+use relision::input::{LineSource, ScriptedSource, StreamSource};
+use relision::loader::Loader;
+use relision::commands::{split_arguments, CallbackResult, ColonCommand};
+use relision::repl::{define_commands, is_incomplete, repl};
+use relision::terms::Locus;
+use common::directory;
+
+#[test]
+fn complete_entries_are_not_continued() {
+    for text in &["a", "a => b", "(a * b) : c", "\"a (\"", "`{`", "a // (", "a /* ( */",
+                  "$x{true}", "a)", "\"\\\"\"", "a >"] {
+        assert!(!is_incomplete(text), "{:?}", text);
+    }
+}
+
+#[test]
+fn incomplete_entries_are_continued() {
+    for text in &["(a", "$x{", "a =>", "a ->", "a *", "a :", "let #1 = a,", "let #1 =",
+                  "\"abc", "`ab\\`", "a /* b", "a => // more\n", "(a\n * b"] {
+        assert!(is_incomplete(text), "{:?}", text);
+    }
+}
+
+/// Run a scripted session and get the prompts shown and the output written.
+fn session(lines: &[&str]) -> (Vec<String>, String) {
+    let mut source = ScriptedSource::new(lines);
+    let mut out = vec![];
+    repl(define_commands(), Loader::new(vec![]), &[], &mut source, &mut out);
+    (source.prompts().to_vec(), String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn entries_are_evaluated() {
+    let (_, out) = session(&["root N;", "rule $x: N -> z;", "a: N * b", "", "b"]);
+    assert_eq!(out, "  -> z * b\n  -> b\nTerminating REPL.\n");
+}
+
+#[test]
+fn multi_line_entries_are_one_history_item() {
+    let mut source = ScriptedSource::new(&["(a *", "   b) =>", "c", ":history"]);
+    let mut out = vec![];
+    repl(define_commands(), Loader::new(vec![]), &[], &mut source, &mut out);
+    assert_eq!(source.prompts(), &["e> ", ".. ", ".. ", "e> ", "e> "]);
+    assert_eq!(source.history(), vec!["(a *\n   b) =>\nc", ":history"]);
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("  -> a * b => c\n0: (a *\n   b) =>\nc\n1: :history\n"), "{}", out);
+}
+
+#[test]
+fn errors_are_reported_and_the_session_continues() {
+    let (_, out) = session(&["a *", ")", "b", ":frob"]);
+    assert!(out.contains("error: expected a term but found `)`\n --> 2:1\n"), "{}", out);
+    assert!(out.contains("  -> b\n"), "{}", out);
+    assert!(out.contains("error: the command :frob was not recognized\n --> 4:1\n"), "{}", out);
+}
+
+#[test]
+fn settings_change_the_output() {
+    let (_, out) = session(&[":set parens full", "a * b * c", ":set rewrite_limit 0",
+                             ":set", ":set nothing 1"]);
+    assert!(out.starts_with("  -> (a * b) * c\n"), "{}", out);
+    assert!(out.contains("parens = full"), "{}", out);
+    assert!(out.contains("rewrite_limit = 0"), "{}", out);
+    assert!(out.contains("error: There is no setting named nothing."), "{}", out);
+}
+
+#[test]
+fn quitting_asks_first() {
+    let (prompts, out) = session(&[":quit", "n", "a", ":q", "y", "b"]);
+    assert_eq!(prompts, vec!["e> ", "Really quit? (y/n) ", "e> ", "e> ",
+                             "Really quit? (y/n) "]);
+    assert_eq!(out, "  -> a\nTerminating REPL.\n");
+}
+
+#[test]
+fn streams_can_drive_a_session() {
+    let mut source = StreamSource::new("a *\r\nb\n:history".as_bytes());
+    let mut out = vec![];
+    repl(define_commands(), Loader::new(vec![]), &[], &mut source, &mut out);
+    assert_eq!(String::from_utf8(out).unwrap(),
+               "  -> a * b\n0: a *\nb\n1: :history\nTerminating REPL.\n");
+}
+
+#[test]
+fn commands_are_found_by_prefix_and_alias() {
+    let commands = define_commands();
+    assert_eq!(commands.lookup("cl").ok().map(|command| command.name), Some("clear"));
+    assert_eq!(commands.lookup("exit").ok().map(|command| command.name), Some("quit"));
+    assert_eq!(commands.lookup("?").ok().map(|command| command.name), Some("help"));
+    assert_eq!(commands.lookup("h").err(), Some(vec!["help", "history"]));
+    assert_eq!(commands.lookup("").err().map(|names| names.len()), Some(9));
+    assert_eq!(commands.lookup("frob").err(), Some(vec![]));
+}
+
+#[test]
+fn ambiguous_commands_list_every_candidate_and_do_not_run() {
+    let (prompts, out) = session(&[":", ":h", "a"]);
+    assert_eq!(prompts.len(), 4, "{:?}", prompts);
+    assert!(out.contains("= note: it might be :clear, :help, :history, :load, :quit, :results, \
+                          :rules, :set, or :type\n"), "{}", out);
+    assert!(out.contains("= note: it might be :help or :history\n"), "{}", out);
+    assert!(out.ends_with("  -> a\nTerminating REPL.\n"), "{}", out);
+}
+
+#[test]
+fn help_is_generated_from_the_registry() {
+    let (_, out) = session(&[":help", ":help :set", ":? exit", ":help frob", ":load"]);
+    assert!(out.starts_with(":clear             Clear the screen.\n"), "{}", out);
+    assert!(out.contains("\n:set [NAME VALUE]  List the writer options"), "{}", out);
+    assert!(out.contains("usage: :set [NAME VALUE]\nList the writer options and settings, or \
+                          change one.\n"), "{}", out);
+    assert!(out.contains("usage: :quit\nLeave the REPL, after asking.\naliases: :exit\n"),
+            "{}", out);
+    assert!(out.contains("error: the command :frob was not recognized"), "{}", out);
+    assert!(out.contains("error: usage: :load PATH"), "{}", out);
+}
+
+#[test]
+fn arguments_are_split_on_spaces_and_quotes() {
+    let texts = |line: &str, words: Option<usize>| -> Vec<String> {
+        split_arguments(line, words).unwrap().into_iter().map(|arg| arg.text).collect()
+    };
+    assert_eq!(texts(":set  parens   full ", None), vec![":set", "parens", "full"]);
+    assert_eq!(texts(":load \"a b.eli\"", None), vec![":load", "a b.eli"]);
+    assert_eq!(texts(":load a\"  \"b", None), vec![":load", "a  b"]);
+    assert_eq!(texts(":load \"tab\\there\\\"\\xE9\"", None), vec![":load", "tab\there\"\u{e9}"]);
+    assert_eq!(texts(":load \"\"", None), vec![":load", ""]);
+    assert_eq!(texts(":type  a * \"b c\"  ", Some(1)), vec![":type", "a * \"b c\""]);
+    let offsets: Vec<(usize, usize)> = split_arguments(":type  \u{e9} * b", Some(1)).unwrap()
+        .iter().map(|arg| (arg.offset, arg.width)).collect();
+    assert_eq!(offsets, vec![(0, 5), (7, 5)]);
+}
+
+#[test]
+fn malformed_arguments_are_reported() {
+    let err = split_arguments(":load \"abc", None).unwrap_err();
+    assert_eq!((err.offset, err.width), (6, 4), "{}", err);
+    let err = split_arguments(":load \"a\\qb\"", None).unwrap_err();
+    assert_eq!((err.offset, err.width), (8, 2), "{}", err);
+    let (_, out) = session(&[":load \"a\\qb\"", ":load \"no such file.eli\"", ":load a b"]);
+    assert!(out.contains("error: unknown escape\n --> 1:9\n"), "{}", out);
+    assert!(out.contains("error: cannot find the file no such file.eli"), "{}", out);
+    assert!(out.contains("error: usage: :load PATH\n --> 3:1\n"), "{}", out);
+}
+
+#[test]
+fn terms_end_command_lines() {
+    let (_, out) = session(&[":type a", ":type  \"x\" * b * )", ":type"]);
+    assert!(out.starts_with("  : SYMBOL\n"), "{}", out);
+    assert!(out.contains("error: expected a term but found `)`\n --> 2:18\n"), "{}", out);
+    assert!(out.contains("error: usage: :type TERM\n"), "{}", out);
+}
+
+#[test]
+fn rules_are_listed_in_order() {
+    let (_, out) = session(&["rule a -> b;", "rule b -> c in X;", "disable X;", ":rules"]);
+    assert!(out.ends_with("0: a -> b in DEFAULT\n1: b -> c in X (disabled)\nTerminating REPL.\n"),
+            "{}", out);
+}
+
+#[test]
+fn commands_can_change_the_session() {
+    let mut commands = define_commands();
+    commands.add(ColonCommand::new("stop", ":stop", "Stop at once.",
+        Box::new(|session, _| -> CallbackResult {
+            session.quit = true;
+            Ok(format!("stopping after {} lines\n", session.line))
+        })));
+    commands.add(ColonCommand::new("root", ":root NAME", "Declare a root.",
+        Box::new(|session, arguments| -> CallbackResult {
+            let entry = format!("root {};", arguments[1].text);
+            session.evaluate(&entry, Locus::Internal);
+            Ok("".to_string())
+        }))
+        .with_arity(1, Some(1)));
+    let mut source = ScriptedSource::new(&[":root N", "a: N", ":stop", "b"]);
+    let mut out = vec![];
+    repl(commands, Loader::new(vec![]), &[], &mut source, &mut out);
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out, "  -> a: N\nstopping after 3 lines\nTerminating REPL.\n");
+}
+
+#[test]
+fn results_are_numbered_and_can_be_used() {
+    let (_, out) = session(&["rule twice * $x -> $x * $x;", "a; b", "twice * $_1", "$_ * $_2",
+                             "$_9", ":results"]);
+    assert!(out.contains("  -> a\n  -> b\n  -> a * a\n  -> a * a * b\n  -> $_9\n"), "{}", out);
+    assert!(out.ends_with("$_1 = a\n$_2 = b\n$_3 = a * a\n$_4 = a * a * b\n$_5 = $_9\n\
+                           Terminating REPL.\n"), "{}", out);
+}
+
+#[test]
+fn results_are_used_in_entries_but_not_in_files() {
+    let dir = directory("repl", "results", &[("uses.eli", "$_1 * $_;\n")]);
+    let file = dir.join("uses.eli").display().to_string();
+    let (_, out) = session(&["a; $_ * $_1", &format!(":load {}", file),
+                             &format!("load \"{}\"; $_", file)]);
+    assert!(out.starts_with("  -> a\n  -> a * a\n  -> $_1 * $_\n  -> $_1 * $_\n  -> $_1 * $_\n"),
+            "{}", out);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn long_results_are_broken_under_the_arrow() {
+    let text = (0..20).map(|index| format!("name{}", index)).collect::<Vec<_>>().join(" * ");
+    let (_, out) = session(&[&text]);
+    let lines: Vec<&str> =
+        out.lines().take_while(|line| !line.starts_with("Terminating")).collect();
+    assert!(lines.len() > 1, "{}", out);
+    assert_eq!(lines[0], "  -> name0 *");
+    assert!(lines.iter().all(|line| line.len() <= 80), "{}", out);
+    for line in &lines[1..] {
+        assert_eq!(line.len() - line.trim_start().len(), 9, "{}", out);
+    }
+}
+
+#[test]
+fn missing_files_are_marked_in_full() {
+    let (_, out) = session(&[":load nowhere.eli", ":load \"no where.eli\""]);
+    assert!(out.contains("1 | :load nowhere.eli\n  |       ^^^^^^^^^^^ not found\n"), "{}", out);
+    assert!(out.contains("2 | :load \"no where.eli\"\n  |       ^^^^^^^^^^^^^^ not found\n"),
+        "{}", out);
+}
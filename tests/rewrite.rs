@@ -0,0 +1,88 @@
+//! Check that a universe rewrites terms with its enabled rules.
+
+extern crate relision;
+
+use relision::Error;
+use relision::terms::{Locus, Universe};
+
+/// Make a universe from declarations, then rewrite a term and write the result.
+fn rewrite(context: &str, text: &str) -> relision::Result<String> {
+    let mut universe = Universe::new();
+    universe.load_from(context, Locus::Internal)?;
+    let term = relision::terms::EliParser::new(text, Locus::Internal)
+        .parse_term(universe.factory())?;
+    let result = universe.rewrite(&term)?;
+    Ok(universe.display(&result).to_string())
+}
+
+#[test]
+fn innermost_rules_apply_until_none_does() {
+    let context = "rule $x * unit -> $x; rule unit * $x -> $x;";
+    assert_eq!(rewrite(context, "(unit * a) * (b * unit)").unwrap(), "a * b");
+    assert_eq!(rewrite(context, "unit * unit * unit").unwrap(), "unit");
+    assert_eq!(rewrite(context, "a => b").unwrap(), "a => b");
+}
+
+#[test]
+fn variables_match_their_type_and_repeat() {
+    let context = "root NAT; rule $x: NAT * $x: NAT -> twice; rule $x * $x -> same;";
+    assert_eq!(rewrite(context, "a: NAT * a: NAT").unwrap(), "twice");
+    assert_eq!(rewrite(context, "a * a").unwrap(), "same");
+    assert_eq!(rewrite(context, "a: NAT * b: NAT").unwrap(), "a: NAT * b: NAT");
+}
+
+#[test]
+fn guards_must_rewrite_to_true() {
+    let context = "rule small -> true; rule a * a -> small; rule $x{$x * $x} * z -> $x;";
+    assert_eq!(rewrite(context, "a * z").unwrap(), "a");
+    assert_eq!(rewrite(context, "b * z").unwrap(), "b * z");
+}
+
+#[test]
+fn only_enabled_rulesets_apply() {
+    let context = "rule a -> b in ON; rule b -> c in OFF; enable ON;";
+    assert_eq!(rewrite(context, "a").unwrap(), "b");
+}
+
+#[test]
+fn the_rewrite_limit_stops_rewriting() {
+    let context = "set rewrite_limit = 5; rule $x * $y -> $y * $x;";
+    match rewrite(context, "a * b") {
+        Err(Error::RewriteLimit(5)) => {}
+        other => panic!("expected the rewrite limit, got {:?}", other),
+    }
+}
+
+#[test]
+fn guards_are_tried_after_the_pattern_matches() {
+    let context = "rule $x{$x * $x} * z -> $x;";
+    assert_eq!(rewrite(context, "b * z").unwrap(), "b * z");
+}
+
+#[test]
+fn shared_subterms_are_rewritten_once() {
+    use relision::terms::{EliParser, Term};
+    use std::sync::Arc;
+    let mut universe = Universe::new();
+    universe.load_from("rule a -> b;", Locus::Internal).unwrap();
+    let mut text = String::from("let #0 = a * a");
+    for level in 1..40 {
+        text.push_str(&format!(", #{} = #{} * #{}", level, level - 1, level - 1));
+    }
+    text.push_str(" in #39");
+    let term = EliParser::new(&text, Locus::Internal).parse_term(universe.factory()).unwrap();
+    let result = universe.rewrite(&term).unwrap();
+    match *result {
+        Term::StaticProduct { ref lhs, ref rhs, .. } => assert!(Arc::ptr_eq(lhs, rhs)),
+        _ => panic!("expected a product"),
+    }
+}
+
+#[test]
+fn guards_nested_too_deeply_stop_rewriting() {
+    let context = "rule $x{$x * z} * z -> $x;";
+    match rewrite(context, "b * z") {
+        Err(Error::GuardDepth(64)) => {}
+        other => panic!("expected the guard depth, got {:?}", other),
+    }
+}
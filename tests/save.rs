@@ -0,0 +1,44 @@
+//! Check that a universe saved to a context and loaded again is the same universe.
+
+extern crate relision;
+
+use relision::terms::{Locus, Universe};
+
+/// Save a universe to a context and get the text.
+fn save(universe: &Universe) -> String {
+    let mut text = vec![];
+    universe.save_to(&mut text).unwrap();
+    String::from_utf8(text).unwrap()
+}
+
+/// Load a universe from a context.
+fn load(text: &str) -> Universe {
+    let mut universe = Universe::new();
+    universe.load_from(text, Locus::Internal).unwrap();
+    universe
+}
+
+#[test]
+fn saved_contexts_load_to_the_same_universe() {
+    let context = r#"
+        root NAT;
+        root `odd name`;
+        operator succ: NAT => NAT "The \"next\" natural.";
+        operator apply: (NAT => NAT) => NAT => NAT;
+        operator pair: (NAT * NAT) * NAT;
+        operator plain: NAT;
+        rule $x: NAT * zero -> zero;
+        rule succ * $x ->{$x * $x} $x in arith, `odd set`;
+        rule ($f => $g) * $y -> $f in lambda;
+        disable arith;
+        enable lambda;
+        set rewrite_limit = "12";
+    "#;
+    let first = save(&load(context));
+    let second = save(&load(&first));
+    assert_eq!(first, second);
+    for expected in &["root NAT;", "(NAT => NAT) => NAT => NAT", "The \\\"next\\\" natural.",
+        " in arith, `odd set`;", "disable arith;", "set rewrite_limit = \"12\";"] {
+        assert!(first.contains(expected), "{} is not in\n{}", expected, first);
+    }
+}
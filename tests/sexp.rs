@@ -0,0 +1,146 @@
+//! Check the S-expression form itself: the types that are written, the quoting of names, deep
+//! nesting, and the white space and comments that the reader skips.
+
+extern crate relision;
+
+use std::sync::Arc;
+use relision::error::Error;
+use relision::terms::{Locus, SexpReader, SexpWriter, Term, TermFactory, TermWriter,
+    WriterConfig};
+
+/// Write a term as an S-expression.
+fn write(writer: &SexpWriter, fact: &TermFactory, term: &Arc<Term>) -> String {
+    let mut out = vec![];
+    writer.write(&mut out, fact, term).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+/// Read a term, failing the test if the text is rejected.
+fn read(fact: &TermFactory, text: &str) -> Arc<Term> {
+    match SexpReader::read_str(text, fact) {
+        Ok(term) => term,
+        Err(err) => panic!("could not read {:?}: {}", text, err),
+    }
+}
+
+/// Read text that must be rejected, and return the message and the locus of the error.
+fn rejected(fact: &TermFactory, text: &str) -> (String, Locus) {
+    match SexpReader::read_str(text, fact) {
+        Err(Error::Parse(diagnostic)) => {
+            let locus = diagnostic.primary.as_ref().unwrap().locus.clone();
+            (diagnostic.message.clone(), locus)
+        }
+        other => panic!("expected a parse error for {:?}, got {:?}", text, other),
+    }
+}
+
+#[test]
+fn every_type_is_written_by_default() {
+    let fact = TermFactory::new();
+    let x = fact.new_variable(Locus::Internal, &fact.get_any(), "x".to_string(),
+        &fact.new_boolean(true));
+    let y = fact.new_symbol(Locus::Internal, "y".to_string());
+    let lambda = fact.new_lambda(Locus::Internal, &x, &y, &fact.new_boolean(true));
+    assert_eq!(write(&SexpWriter::new(), &fact, &lambda),
+        "(lambda (var x ANY true) (symbol y SYMBOL) true)");
+    let odd = fact.new_typed_boolean(Locus::Internal, true, fact.get_string());
+    assert_eq!(write(&SexpWriter::new(), &fact, &odd), "(boolean true STRING)");
+}
+
+#[test]
+fn names_that_need_them_are_written_in_bars() {
+    let fact = TermFactory::new();
+    let writer = SexpWriter::new();
+    for &(name, text) in &[
+        ("fred", "(symbol fred SYMBOL)"),
+        ("a-b_c+d*e/f<g>h=i!j?k.l", "(symbol a-b_c+d*e/f<g>h=i!j?k.l SYMBOL)"),
+        ("two words", "(symbol |two words| SYMBOL)"),
+        ("true", "(symbol |true| SYMBOL)"),
+        ("false", "(symbol |false| SYMBOL)"),
+        ("", "(symbol || SYMBOL)"),
+        ("(x)", "(symbol |(x)| SYMBOL)"),
+        ("a;b", "(symbol |a;b| SYMBOL)"),
+        ("x|y", "(symbol |x\\|y| SYMBOL)"),
+        ("back\\slash", "(symbol |back\\\\slash| SYMBOL)"),
+        ("caf\u{E9}", "(symbol |caf\\u{00E9}| SYMBOL)"),
+    ] {
+        let symbol = fact.new_symbol(Locus::Internal, name.to_string());
+        assert_eq!(write(&writer, &fact, &symbol), text);
+        assert_eq!(read(&fact, text), symbol);
+    }
+    let unicode = SexpWriter::new().with_config(WriterConfig { ascii: false,
+        ..SexpWriter::new().config().clone() });
+    let symbol = fact.new_symbol(Locus::Internal, "caf\u{E9}".to_string());
+    assert_eq!(write(&unicode, &fact, &symbol), "(symbol |caf\u{E9}| SYMBOL)");
+    // Bars keep a name from being taken for a Boolean or a named root term.
+    assert_eq!(read(&fact, "(symbol |ANY|)"), fact.get_any());
+    assert!(SexpReader::read_str("|true|", &fact).is_err());
+    assert!(SexpReader::read_str("|ANY|", &fact).is_ok());
+}
+
+#[test]
+fn deep_nesting_is_written_and_read() {
+    let fact = TermFactory::new();
+    let mut term = fact.new_symbol(Locus::Internal, "leaf".to_string());
+    for _ in 0..500 {
+        term = fact.new_static_product(Locus::Internal, &term, &fact.get_root());
+    }
+    let text = write(&SexpWriter::new(), &fact, &term);
+    assert!(text.starts_with(&"(product ".repeat(500)));
+    assert!(text.ends_with(&" ^ROOT)".repeat(500)));
+    assert_eq!(read(&fact, &text), term);
+}
+
+#[test]
+fn white_space_and_comments_are_skipped() {
+    let fact = TermFactory::new();
+    let expected = fact.new_static_map(Locus::Internal, &fact.get_string(), &fact.get_symbol());
+    for text in &[
+        "(map STRING SYMBOL)",
+        "  \t\n( map\r\n\tSTRING   SYMBOL )\n\n",
+        "; a line comment (with a parenthesis\n(map STRING ; another )\n SYMBOL)",
+        "(map STRING SYMBOL) ; a comment at the end without a newline",
+        "#| a block comment |#(map#|inside|#STRING SYMBOL)",
+        "#| a block comment\n   over ( lines ; with other comments\n|# (map STRING SYMBOL)",
+        "(map STRING;the comment ends the atom\nSYMBOL)",
+        "(map STRING #||# SYMBOL)#| at the end |#",
+    ] {
+        assert_eq!(read(&fact, text), expected, "reading {:?}", text);
+    }
+}
+
+#[test]
+fn loci_are_written_as_comments_and_skipped() {
+    let fact = TermFactory::new();
+    let locus = Locus::File("test.eli".to_string(), 3, 7);
+    let symbol = fact.new_symbol(locus.clone(), "x".to_string());
+    let pair = fact.new_static_product(locus, &symbol, &fact.get_any());
+    let writer = SexpWriter::new().with_config(WriterConfig { loci: true,
+        ..SexpWriter::new().config().clone() });
+    let text = write(&writer, &fact, &pair);
+    assert_eq!(text, format!("#| {0} |# (product #| {0} |# (symbol x SYMBOL) ANY)",
+        fact.get_locus(&pair)));
+    let internal = fact.new_static_product(Locus::Internal,
+        &fact.new_symbol(Locus::Internal, "x".to_string()), &fact.get_any());
+    assert_eq!(read(&fact, &text), internal);
+}
+
+#[test]
+fn errors_are_placed_after_comments() {
+    let fact = TermFactory::new();
+    assert_eq!(rejected(&fact, "; one\n#| two\n |# (wibble)"),
+        ("unknown kind of term wibble".to_string(), Locus::Console(3, 5)));
+    assert_eq!(rejected(&fact, "#| never closed"),
+        ("expected a term but found the end of the input".to_string(), Locus::Console(1, 16)));
+    assert_eq!(rejected(&fact, "(map STRING ; SYMBOL)"),
+        ("missing closing parenthesis".to_string(), Locus::Console(1, 1)));
+}
+
+#[test]
+fn malformed() {
+    let fact = TermFactory::new();
+    assert!(SexpReader::read_str("(lambda (var x) y)", &fact).is_err());
+    assert!(SexpReader::read_str("(symbol", &fact).is_err());
+    assert!(SexpReader::read_str("(wibble a)", &fact).is_err());
+    assert!(SexpReader::read_str("", &fact).is_err());
+}